@@ -0,0 +1,30 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+
+#[derive(Properties, PartialEq)]
+pub struct BoardCodeInputProps {
+    pub code: Option<String>,
+    pub on_code_entered: Callback<String>,
+}
+
+#[function_component(BoardCodeInput)]
+pub fn board_code_input(BoardCodeInputProps { code, on_code_entered }: &BoardCodeInputProps) -> Html {
+    let code = code.clone().unwrap_or_default();
+    let onchange = on_code_entered.reform(|e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        input.value()
+    });
+    let link = if code.is_empty() {
+        html! {}
+    } else {
+        html! { <a href={format!("?board={}", code)}>{"Link"}</a> }
+    };
+
+    html! {
+        <div class="board-code">
+            <label for="boardCodeInput">{"Board"}</label>
+            <input id="boardCodeInput" type="text" placeholder="Paste a board code" value={code} {onchange} />
+            { link }
+        </div>
+    }
+}
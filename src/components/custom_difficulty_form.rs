@@ -0,0 +1,57 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use minesweeper_rs::models::settings::{Dimensions, Settings, MAX_DEPTH, MAX_HEIGHT, MAX_MINES, MAX_WIDTH};
+
+#[derive(Properties, PartialEq)]
+pub struct CustomDifficultyFormProps {
+    pub dimensions: Dimensions,
+    pub settings: Settings,
+    pub on_dimensions_entered: Callback<Dimensions>,
+}
+
+#[function_component(CustomDifficultyForm)]
+pub fn custom_difficulty_form(
+    CustomDifficultyFormProps { dimensions, settings, on_dimensions_entered }: &CustomDifficultyFormProps
+) -> Html {
+    let width_ref = use_node_ref();
+    let height_ref = use_node_ref();
+    let depth_ref = use_node_ref();
+    let mines_ref = use_node_ref();
+    let error = use_state(|| None::<String>);
+
+    let onsubmit = {
+        let (width_ref, height_ref, depth_ref, mines_ref) = (width_ref.clone(), height_ref.clone(), depth_ref.clone(), mines_ref.clone());
+        let error = error.clone();
+        let settings = *settings;
+        let on_dimensions_entered = on_dimensions_entered.clone();
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+            let read = |node_ref: &NodeRef| node_ref.cast::<HtmlInputElement>().and_then(|input| input.value().trim().parse::<usize>().ok());
+            let (Some(width), Some(height), Some(depth), Some(mines)) = (read(&width_ref), read(&height_ref), read(&depth_ref), read(&mines_ref)) else {
+                error.set(Some("Width, height, layers and mines must be whole numbers".into()));
+                return;
+            };
+
+            match settings.validate_dimensions(width, height, depth, mines) {
+                Ok(dimensions) => {
+                    error.set(None);
+                    on_dimensions_entered.emit(dimensions);
+                },
+                Err(validation_error) => { error.set(Some(validation_error.to_string())); },
+            }
+        })
+    };
+
+    html! {
+        <form class="custom-difficulty" {onsubmit}>
+            <label>{"W"}<input ref={width_ref} type="number" min="1" max={MAX_WIDTH.to_string()} value={dimensions.width().to_string()} /></label>
+            <label>{"H"}<input ref={height_ref} type="number" min="1" max={MAX_HEIGHT.to_string()} value={dimensions.height().to_string()} /></label>
+            <label>{"Layers"}<input ref={depth_ref} type="number" min="1" max={MAX_DEPTH.to_string()} value={dimensions.depth().to_string()} /></label>
+            <label>{"Mines"}<input ref={mines_ref} type="number" min="0" max={MAX_MINES.to_string()} value={dimensions.mines().to_string()} /></label>
+            <button type="submit">{"Start"}</button>
+            if let Some(error) = &*error {
+                <div class="custom-difficulty-error">{ error }</div>
+            }
+        </form>
+    }
+}
@@ -1,5 +1,5 @@
 use yew::prelude::*;
-use crate::models::settings::{Difficulty, Dimensions};
+use minesweeper_rs::models::settings::Difficulty;
 
 #[derive(Properties, PartialEq)]
 pub struct DifficultyOptionProps {
@@ -15,13 +15,8 @@ pub fn difficulty_option(
     html! {
         <a class={classes!("difficulty", classes)}
             onclick={
-                // todo!("This only works because custom is hard-coded to have the default dimensions.")
-                match difficulty {
-                    Difficulty::Beginner => { on_difficulty_selected.reform(|_| Difficulty::Beginner) },
-                    Difficulty::Intermediate => { on_difficulty_selected.reform(|_| Difficulty::Intermediate) },
-                    Difficulty::Expert => { on_difficulty_selected.reform(|_| Difficulty::Expert) },
-                    Difficulty::Custom(_) => { on_difficulty_selected.reform(|_| Difficulty::Custom(Dimensions::default())) },
-                }
+                let difficulty = *difficulty;
+                on_difficulty_selected.reform(move |_| difficulty)
             }
         >
             {difficulty.title()}
@@ -1,3 +1,10 @@
+pub mod board_code_input;
 pub mod cell;
 pub mod counter;
-pub mod difficulty_option;
\ No newline at end of file
+pub mod custom_difficulty_form;
+pub mod difficulty_option;
+pub mod replay_controls;
+pub mod seed_input;
+pub mod settings_panel;
+pub mod stats_panel;
+pub mod summary_panel;
\ No newline at end of file
@@ -0,0 +1,41 @@
+use yew::prelude::*;
+
+pub const REPLAY_SPEEDS: [u64; 5] = [25, 50, 100, 200, 400];
+
+#[derive(Properties, PartialEq)]
+pub struct ReplayControlsProps {
+    pub step: usize,
+    pub steps: usize,
+    pub playing: bool,
+    /// Playback speed as a percentage of real time.
+    pub speed: u64,
+    pub on_step_back: Callback<()>,
+    pub on_toggle_play: Callback<()>,
+    pub on_step_forward: Callback<()>,
+    pub on_speed_selected: Callback<u64>,
+    pub on_close: Callback<()>,
+}
+
+#[function_component(ReplayControls)]
+pub fn replay_controls(props: &ReplayControlsProps) -> Html {
+    let speeds = REPLAY_SPEEDS.iter().map(|speed| {
+        let speed = *speed;
+        let highlight = if speed == props.speed { "highlight" } else { "" };
+        html! {
+            <a class={classes!("replay-speed", highlight)} onclick={props.on_speed_selected.reform(move |_| speed)}>
+                { format!("{}x", speed as f64 / 100.0) }
+            </a>
+        }
+    });
+
+    html! {
+        <div class="replay-controls">
+            <a onclick={props.on_step_back.reform(|_| ())}>{"⏮"}</a>
+            <a onclick={props.on_toggle_play.reform(|_| ())}>{ if props.playing { "⏸" } else { "▶" } }</a>
+            <a onclick={props.on_step_forward.reform(|_| ())}>{"⏭"}</a>
+            <span>{ format!("{}/{}", props.step, props.steps) }</span>
+            { for speeds }
+            <a onclick={props.on_close.reform(|_| ())}>{"✕"}</a>
+        </div>
+    }
+}
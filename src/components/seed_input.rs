@@ -0,0 +1,31 @@
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+
+#[derive(Properties, PartialEq)]
+pub struct SeedInputProps {
+    pub seed: u64,
+    pub on_seed_entered: Callback<u64>,
+}
+
+#[function_component(SeedInput)]
+pub fn seed_input(SeedInputProps { seed, on_seed_entered }: &SeedInputProps) -> Html {
+    let seed = *seed;
+    let onchange = on_seed_entered.reform(move |e: Event| {
+        let input: HtmlInputElement = e.target_unchecked_into();
+        match input.value().trim().parse::<u64>() {
+            Ok(entered_seed) => entered_seed,
+            Err(_) => {
+                // Put the current seed back rather than leaving garbage in the box
+                input.set_value(&seed.to_string());
+                seed
+            },
+        }
+    });
+
+    html! {
+        <div class="seed">
+            <label for="seedInput">{"Seed"}</label>
+            <input id="seedInput" type="text" inputmode="numeric" value={seed.to_string()} {onchange} />
+        </div>
+    }
+}
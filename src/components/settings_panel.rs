@@ -0,0 +1,188 @@
+use yew::prelude::*;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use minesweeper_rs::models::key_bindings::{KeyAction, KeyBindings};
+use minesweeper_rs::models::neighborhood::{NeighborMask, Neighborhood};
+use minesweeper_rs::models::settings::{ChordSetting, FirstClickSetting, Settings, MAX_MINES_PER_CELL};
+use minesweeper_rs::models::topology::Topology;
+
+const CHORD_SETTINGS: [ChordSetting; 5] = [
+    ChordSetting::LeftClick,
+    ChordSetting::LeftAndRightClick,
+    ChordSetting::MiddleClick,
+    ChordSetting::LeftOrMiddleClick,
+    ChordSetting::Disabled,
+];
+const TOPOLOGIES: [Topology; 2] = [Topology::Square, Topology::Hex];
+// Followed by a custom option, which starts from whichever shape was picked before
+const NEIGHBORHOODS: [Neighborhood; 4] = [
+    Neighborhood::Moore,
+    Neighborhood::VonNeumann,
+    Neighborhood::Knight,
+    Neighborhood::Radius2,
+];
+const FIRST_CLICK_SETTINGS: [FirstClickSetting; 3] = [FirstClickSetting::Any, FirstClickSetting::Safe, FirstClickSetting::Zero];
+
+#[derive(Properties, PartialEq)]
+pub struct SettingsPanelProps {
+    pub settings: Settings,
+    pub on_settings_changed: Callback<Settings>,
+    pub key_bindings: KeyBindings,
+    pub on_key_bindings_changed: Callback<KeyBindings>,
+}
+
+#[function_component(SettingsPanel)]
+pub fn settings_panel(
+    SettingsPanelProps { settings, on_settings_changed, key_bindings, on_key_bindings_changed }: &SettingsPanelProps
+) -> Html {
+    let settings = *settings;
+    // The action waiting for its new key, if any
+    let rebinding = use_state(|| None::<KeyAction>);
+
+    let on_chord_changed = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        settings.set_chord_setting(CHORD_SETTINGS[selected_index(&e)]);
+        settings
+    });
+    let on_first_click_changed = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        settings.set_first_click_setting(FIRST_CLICK_SETTINGS[selected_index(&e)]);
+        settings
+    });
+    let on_topology_changed = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        settings.set_topology(TOPOLOGIES[selected_index(&e)]);
+        settings
+    });
+    let on_neighborhood_changed = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        let custom = Neighborhood::Custom(settings.neighborhood().mask());
+        settings.set_neighborhood(NEIGHBORHOODS.get(selected_index(&e)).copied().unwrap_or(custom));
+        settings
+    });
+    let is_custom_neighborhood = matches!(settings.neighborhood(), Neighborhood::Custom(_));
+    let mask = settings.neighborhood().mask();
+    let mask_cells = NeighborMask::grid().map(|offset| {
+        if offset == (0, 0) { return html! { <span class="neighbor-mask-cell neighbor-mask-center" /> }; }
+        let onclick = on_settings_changed.reform(move |_: MouseEvent| {
+            let mut settings = settings;
+            settings.set_neighborhood(Neighborhood::Custom(mask.toggled(offset)));
+            settings
+        });
+        html! { <button type="button" class={classes!("neighbor-mask-cell", mask.contains(offset).then_some("neighbor-mask-on"))} {onclick} /> }
+    });
+    let on_mines_per_cell_changed = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        settings.set_max_mines_per_cell(selected_index(&e) + 1);
+        settings
+    });
+    let on_wrap_toggled = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        settings.set_wrap_edges(e.target_unchecked_into::<HtmlInputElement>().checked());
+        settings
+    });
+    let on_unknown_toggled = on_settings_changed.reform(move |e: Event| {
+        let mut settings = settings;
+        settings.set_allow_mark_cell_as_unknown(e.target_unchecked_into::<HtmlInputElement>().checked());
+        settings
+    });
+
+    let key_rows = KeyAction::ALL.iter().map(|action| {
+        let action = *action;
+        let onclick = {
+            let rebinding = rebinding.clone();
+            Callback::from(move |_: MouseEvent| rebinding.set(Some(action)))
+        };
+        let onkeydown = {
+            let rebinding = rebinding.clone();
+            let key_bindings = key_bindings.clone();
+            let on_key_bindings_changed = on_key_bindings_changed.clone();
+            Callback::from(move |e: KeyboardEvent| {
+                if *rebinding != Some(action) || e.key() == "Tab" { return; }
+                // Keep the key from also playing the game
+                e.prevent_default();
+                e.stop_propagation();
+                rebinding.set(None);
+                if e.key() == "Escape" { return; }
+
+                let mut key_bindings = key_bindings.clone();
+                key_bindings.set_key(action, &e.key());
+                on_key_bindings_changed.emit(key_bindings);
+            })
+        };
+        let key = if *rebinding == Some(action) { "Press a key".to_string() } else { key_bindings.key_title(action) };
+
+        html! {
+            <label class="key-binding">
+                { action.title() }
+                <button type="button" {onclick} {onkeydown}>{ key }</button>
+            </label>
+        }
+    });
+
+    html! {
+        <div class="settings-panel">
+            <label title="Changing the grid starts a new game">
+                {"Grid"}
+                <select onchange={on_topology_changed}>
+                    { for TOPOLOGIES.iter().map(|topology| html! {
+                        <option selected={*topology == settings.topology()}>{ topology.title() }</option>
+                    }) }
+                </select>
+            </label>
+            <label title="Which cells a number counts on a square grid. Changing this starts a new game">
+                {"Neighbors"}
+                <select onchange={on_neighborhood_changed} disabled={settings.neighborhood_is_disabled()}>
+                    { for NEIGHBORHOODS.iter().map(|neighborhood| html! {
+                        <option selected={*neighborhood == settings.neighborhood()}>{ neighborhood.title() }</option>
+                    }) }
+                    <option selected={is_custom_neighborhood}>{ Neighborhood::Custom(mask).title() }</option>
+                </select>
+            </label>
+            if is_custom_neighborhood && !settings.neighborhood_is_disabled() {
+                <div class="neighbor-mask" title="Click the squares around the middle cell to choose its neighbors">
+                    { for mask_cells }
+                </div>
+            }
+            <label title="Flags cycle up to this many too. Changing this starts a new game">
+                {"Mines per cell"}
+                <select onchange={on_mines_per_cell_changed}>
+                    { for (1..=MAX_MINES_PER_CELL).map(|mines| html! {
+                        <option selected={mines == settings.max_mines_per_cell()}>{ if mines == 1 { "One".to_string() } else { format!("Up to {}", mines) } }</option>
+                    }) }
+                </select>
+            </label>
+            <label>
+                {"Chord with"}
+                <select onchange={on_chord_changed}>
+                    { for CHORD_SETTINGS.iter().map(|chord_setting| html! {
+                        <option selected={*chord_setting == settings.chord_setting()}>{ chord_setting.title() }</option>
+                    }) }
+                </select>
+            </label>
+            <label title="Opposite edges touch. Changing this starts a new game">
+                <input type="checkbox" checked={settings.wrap_edges()} onchange={on_wrap_toggled} />
+                {"Wrap edges"}
+            </label>
+            <label title="Takes effect from the next game">
+                {"First click"}
+                <select onchange={on_first_click_changed} disabled={settings.generation_setting_is_no_guess()}>
+                    { for FIRST_CLICK_SETTINGS.iter().map(|first_click_setting| html! {
+                        <option selected={*first_click_setting == settings.first_click_setting()}>{ first_click_setting.title() }</option>
+                    }) }
+                </select>
+            </label>
+            <label>
+                <input type="checkbox" checked={settings.allow_mark_cell_as_unknown()} onchange={on_unknown_toggled} />
+                {"Question marks"}
+            </label>
+            <div class="key-bindings" title="The arrow keys always move the cursor too">
+                { for key_rows }
+            </div>
+        </div>
+    }
+}
+
+fn selected_index(e: &Event) -> usize {
+    // The options are listed in the same order as the arrays above
+    e.target_unchecked_into::<HtmlSelectElement>().selected_index().max(0) as usize
+}
@@ -0,0 +1,50 @@
+use yew::prelude::*;
+use minesweeper_rs::models::stats::DifficultyStats;
+
+#[derive(Properties, PartialEq)]
+pub struct StatsPanelProps {
+    pub title: String,
+    pub stats: DifficultyStats,
+}
+
+#[function_component(StatsPanel)]
+pub fn stats_panel(StatsPanelProps { title, stats }: &StatsPanelProps) -> Html {
+    let best_time = stats.best_time().map_or("-".to_string(), |best_time| format_seconds(best_time.milliseconds()));
+    let best_times = stats.best_times().iter().enumerate().map(|(i, best_time)| {
+        html! {
+            <tr key={i}>
+                <td>{ i + 1 }</td>
+                <td>{ format_seconds(best_time.milliseconds()) }</td>
+                <td>{ format_date(best_time.date()) }</td>
+            </tr>
+        }
+    });
+
+    html! {
+        <div class="stats">
+            <div class="stats-title">{ format!("{} stats", title) }</div>
+            <table class="stats-summary">
+                <tr><td>{"Played"}</td><td>{ stats.played() }</td></tr>
+                <tr><td>{"Won"}</td><td>{ stats.won() }</td></tr>
+                <tr><td>{"Win rate"}</td><td>{ format!("{:.0}%", stats.win_rate() * 100.0) }</td></tr>
+                <tr><td>{"Current streak"}</td><td>{ stats.current_streak() }</td></tr>
+                <tr><td>{"Longest streak"}</td><td>{ stats.longest_streak() }</td></tr>
+                <tr><td>{"Best time"}</td><td>{ best_time }</td></tr>
+            </table>
+            if !stats.best_times().is_empty() {
+                <table class="stats-best-times">
+                    { for best_times }
+                </table>
+            }
+        </div>
+    }
+}
+
+fn format_seconds(milliseconds: u64) -> String {
+    format!("{:.3}s", milliseconds as f64 / 1000.0)
+}
+
+fn format_date(date: u64) -> String {
+    let date = js_sys::Date::new(&(date as f64).into());
+    String::from(date.to_locale_date_string("default", &js_sys::Object::new()))
+}
@@ -0,0 +1,32 @@
+use yew::prelude::*;
+use minesweeper_rs::models::metrics::GameSummary;
+
+#[derive(Properties, PartialEq)]
+pub struct SummaryPanelProps {
+    pub summary: GameSummary,
+}
+
+#[function_component(SummaryPanel)]
+pub fn summary_panel(SummaryPanelProps { summary }: &SummaryPanelProps) -> Html {
+    let clicks = summary.clicks();
+    let three_bv = if summary.solved_three_bv() == summary.three_bv() {
+        summary.three_bv().to_string()
+    } else {
+        format!("{} / {}", summary.solved_three_bv(), summary.three_bv())
+    };
+
+    html! {
+        <div class="stats summary">
+            <table class="stats-summary">
+                <tr><td>{"3BV"}</td><td>{ three_bv }</td></tr>
+                <tr><td>{"3BV/s"}</td><td>{ format!("{:.2}", summary.three_bv_per_second()) }</td></tr>
+                <tr>
+                    <td>{"Clicks"}</td>
+                    <td title="Left, right and chord">{ format!("{} ({} + {} + {})", clicks.total(), clicks.left(), clicks.right(), clicks.chord()) }</td>
+                </tr>
+                <tr><td>{"Efficiency"}</td><td>{ format!("{:.0}%", summary.efficiency()) }</td></tr>
+                <tr><td>{"IOE"}</td><td>{ format!("{:.2}", summary.ioe()) }</td></tr>
+            </table>
+        </div>
+    }
+}
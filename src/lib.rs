@@ -0,0 +1,26 @@
+//! The rules of minesweeper, independent of any frontend.
+//!
+//! The browser frontend lives in the `minesweeper_rs` binary and is only built with the `web` feature.
+//! Depend on this crate with `default-features = false` to use [`Game`] without pulling in a browser stack.
+
+pub mod models;
+
+pub use models::board_code::{BoardCode, BoardCodeError};
+pub use models::cell::{Cell, DisplayState, Value};
+pub use models::face::Face;
+pub use models::game::{Clock, Game, GameState, LoadBoardError, Position};
+pub use models::history::{History, Snapshot};
+pub use models::key_bindings::{KeyAction, KeyBindings};
+pub use models::metrics::{openings, three_bv, ClickCounts, GameSummary};
+pub use models::neighborhood::{NeighborMask, Neighborhood};
+pub use models::probability::mine_probabilities;
+pub use models::replay::{Action, ActionKind, Replay, ReplayPlayer};
+pub use models::saved_game::SavedGame;
+pub use models::settings::{ChordSetting, Difficulty, DifficultySetting, Dimensions, DimensionsError, FirstClickSetting, GenerationSetting, Settings};
+pub use models::simulation::{BoardSample, Distribution, FirstClick, SimulationReport};
+pub use models::solver::{Deduction, Hint, Reason, Solver};
+pub use models::stats::{BestTime, DifficultyStats, Stats};
+pub use models::stopwatch::Stopwatch;
+pub use models::storage::{MemoryStorage, Storage};
+pub use models::topology::Topology;
+pub use models::touch_state::{InputMode, TouchPress};
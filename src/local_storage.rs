@@ -0,0 +1,22 @@
+use gloo::storage::{LocalStorage as GlooLocalStorage, Storage as GlooStorage};
+use minesweeper_rs::models::storage::Storage;
+
+/// The browser's `localStorage`. Failures (private browsing, full quota) are logged and otherwise ignored.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LocalStorage;
+
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        GlooLocalStorage::raw().get_item(key).ok().flatten()
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        if GlooLocalStorage::raw().set_item(key, value).is_err() {
+            log::warn!("Couldn't write {} to localStorage", key);
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        GlooLocalStorage::raw().remove_item(key).ok();
+    }
+}
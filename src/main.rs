@@ -1,218 +1,324 @@
+// yew 0.19's html! macro expands component props into bare statements
+#![allow(clippy::unnecessary_operation)]
+
 mod components;
-mod models;
+mod local_storage;
 
+use components::board_code_input::BoardCodeInput;
 use components::counter::Counter;
+use components::custom_difficulty_form::CustomDifficultyForm;
 use components::difficulty_option::DifficultyOption;
-use models::face::Face;
-use models::cell::Cell as Cell;
-use models::mouse_state::MouseState;
-use models::settings::{Difficulty, Settings, Dimensions};
+use components::replay_controls::ReplayControls;
+use components::seed_input::SeedInput;
+use components::settings_panel::SettingsPanel;
+use components::stats_panel::StatsPanel;
+use components::summary_panel::SummaryPanel;
+use local_storage::LocalStorage;
+use minesweeper_rs::models::face::Face;
+use minesweeper_rs::models::board_code::BoardCode;
+use minesweeper_rs::models::cell::Cell as Cell;
+use minesweeper_rs::models::game::{Clock, Game, GameState};
+use minesweeper_rs::models::history::{History, Snapshot};
+use minesweeper_rs::models::key_bindings::{KeyAction, KeyBindings};
+use minesweeper_rs::models::metrics::{ClickCounts, GameSummary};
+use minesweeper_rs::models::mouse_state::MouseState;
+use minesweeper_rs::models::probability::mine_probabilities;
+use minesweeper_rs::models::replay::{ActionKind, Replay, ReplayPlayer};
+use minesweeper_rs::models::saved_game::SavedGame;
+use minesweeper_rs::models::settings::{Difficulty, Dimensions, DimensionsError, GenerationSetting, Settings};
+use minesweeper_rs::models::solver::{Hint, Solver};
+use minesweeper_rs::models::stats::Stats;
+use minesweeper_rs::models::stopwatch::Stopwatch;
+use minesweeper_rs::models::storage::Storage;
+use minesweeper_rs::models::topology::Topology;
+use minesweeper_rs::models::touch_state::{InputMode, TouchPress, LONG_PRESS_MILLISECONDS};
 use wasm_bindgen::JsCast;
 use yew::{html, Component, Context, Html, classes};
-use web_sys::{Element, MouseEvent};
+use web_sys::{Element, KeyboardEvent, MouseEvent, TouchEvent};
 // use gloo_console as console;
-use gloo::timers::callback::Interval;
-use rand::Rng;
-use std::{collections::HashSet, cmp};
+use gloo::events::EventListener;
+use gloo::timers::callback::{Interval, Timeout};
+use std::cmp;
 
 enum Msg {
     Tick,
     MouseDown(usize, MouseEvent),
     MouseUp(usize, MouseEvent),
     MouseMove(MouseEvent),
+    KeyDown(KeyboardEvent),
+    TouchStart(usize, TouchEvent),
+    TouchMove(TouchEvent),
+    TouchEnd(TouchEvent),
+    TouchCancel,
+    LongPress,
+    ToggleInputMode,
+    TogglePractice,
+    Undo,
+    Redo,
     Reset,
     Ignore,
     ForceRender,
     ChangeSize(Difficulty),
+    ChangeCustomDimensions(Dimensions),
+    SetSeed(u64),
+    LoadBoard(String),
+    ChangeGeneration(GenerationSetting),
+    ChangeSettings(Settings),
+    ChangeKeyBindings(KeyBindings),
+    ChangeLayer(usize),
+    Hint,
+    ToggleProbabilities,
+    OpenReplay,
+    CloseReplay,
+    ReplayStepBack,
+    ReplayStepForward,
+    ReplayTogglePlay,
+    ReplaySpeed(u64),
+    ReplayTick,
+    ToggleStats,
+    ToggleSettings,
+    SaveGame,
 }
 
+// The tick only refreshes the counter, the time itself comes from the stopwatch
+const TICK_MILLISECONDS: u32 = 100;
+const REPLAY_TICK_MILLISECONDS: u64 = 50;
+const CUSTOM_DIMENSIONS_KEY: &str = "minesweeper_rs.custom_dimensions";
+const SETTINGS_KEY: &str = "minesweeper_rs.settings";
+
 struct App {
-    active:                     bool,
+    game:                       Game,
     face:                       Face,
-    cells:                      Vec<Cell>,
-    neighbors:                  Vec<HashSet<usize>>,
-    mine_indices:               Vec<usize>,
-    shown_cells_count:          usize,
     selected_cell_index:        Option<usize>,
-    first_clicked_mine_index:   Option<usize>,
     seconds_played:             usize,
     mouse_state:                MouseState,
-    settings:                   Settings,
     interval:                   Option<Interval>,
+    hint:                       Option<Hint>,
+    hint_message:               Option<String>,
+    probabilities:              Option<Vec<Option<f64>>>,
+    stopwatch:                  Stopwatch,
+    replay:                     Option<Replay>,
+    replay_player:              Option<ReplayPlayer>,
+    replay_speed:               u64,
+    replay_interval:            Option<Interval>,
+    storage:                    LocalStorage,
+    stats:                      Stats,
+    show_stats:                 bool,
+    custom_dimensions:          Dimensions,
+    show_settings:              bool,
+    key_bindings:               KeyBindings,
+    cursor_index:               Option<usize>,
+    layer:                      usize,
+    input_mode:                 InputMode,
+    touch_press:                Option<TouchPress>,
+    long_press_timeout:         Option<Timeout>,
+    practice:                   bool,
+    history:                    History,
+    unranked:                   bool,
+    chosen_board:               bool,
+    clicks:                     ClickCounts,
+    _pagehide_listener:         EventListener,
+    _keydown_listener:          EventListener,
 }
 
 impl App {
     fn reset_interval(&mut self, ctx: &Context<Self>) {
         let callback = ctx.link().callback(|_| Msg::Tick);
-        let interval = Interval::new(1000, move || callback.emit(()));
+        let interval = Interval::new(TICK_MILLISECONDS, move || callback.emit(()));
         self.interval = Some(interval);
     }
 
-    fn reassign_cells(&mut self, index_clicked: usize) {
-        let (cells, neighbors, mine_indices) = self.generate_cells(index_clicked);
-        self.cells = cells;
-        self.neighbors = neighbors;
-        self.mine_indices = mine_indices;
+    // The game being watched in the replay viewer, or else the one being played
+    fn displayed_game(&self) -> &Game {
+        self.replay_player.as_ref().map_or(&self.game, |player| player.game())
     }
 
-    fn clear_cells(&mut self) {
-        for cell in self.cells.iter_mut() {
-            cell.reset();
-        }
-        for mine_index in self.mine_indices.iter_mut() {
-            // Setting to 0 because it will be updated in reassign_cells
-            *mine_index = 0;
-        }
+    // A replay can be of a board with fewer layers than the one being played
+    fn displayed_layer(&self) -> usize {
+        self.layer.min(self.displayed_game().settings().dimensions().depth() - 1)
     }
 
-    fn generate_cells(&self, index_clicked: usize) -> (Vec<Cell>, Vec<HashSet<usize>>, Vec<usize>) {
-        let mut cells: Vec<Cell> = Vec::new();
-        let mut neighbors: Vec<HashSet<usize>> = Vec::new();
-        let mut mine_indices: Vec<usize> = Vec::new();
-        let mut current_mine_indices: HashSet<usize> = HashSet::new();
-        let index_neighbors = self.calculate_neighbors(index_clicked);
-        for _ in 0..self.mine_indices.len() {
-            let mut i = self.get_random_cell_index();
-            while !self.index_can_be_mine(index_clicked, i, &current_mine_indices, &index_neighbors) {
-                i = self.get_random_cell_index();
-            }
-            current_mine_indices.insert(i);
-        }
-
-        for cell_index in 0..self.cells.len() {
-            let neighboring_cells = self.calculate_neighbors(cell_index);
-            let neighboring_mines = if current_mine_indices.contains(&cell_index) {
-                None
-            } else {
-                Some(neighboring_cells.intersection(&current_mine_indices).count())
-            };
-            let cell = Cell::new(neighboring_mines);
-            cells.push(cell);
-            neighbors.push(neighboring_cells);
-            if current_mine_indices.contains(&cell_index) { mine_indices.push(cell_index); }
-        }
+    // The cursor and selection point into the game being played, which a replay's board may be smaller than
+    fn displayed_selected_index(&self) -> Option<usize> {
+        self.selected_cell_index.filter(|_| self.replay_player.is_none())
+    }
 
-        (cells, neighbors, mine_indices)
+    fn displayed_focus_index(&self) -> Option<usize> {
+        self.cursor_index.or(self.selected_cell_index).filter(|_| self.replay_player.is_none())
     }
 
-    fn index_can_be_mine(&self, index_clicked: usize, mine_index: usize, current_mine_indices: &HashSet<usize>, neighbors: &HashSet<usize>) -> bool {
-        if current_mine_indices.contains(&mine_index) { return false; }
-        if index_clicked == mine_index { return self.settings.first_click_setting_is_any(); }
-        if neighbors.contains(&mine_index) && self.settings.first_click_setting_is_zero() { return false; }
-        true
+    fn neighbors_selected_cell(&self, index: usize) -> bool {
+        let Some(selected_index) = self.displayed_selected_index() else { return false; };
+        if index == selected_index { return true; }
+
+        let neigbors = self.displayed_game().neighbors(selected_index);
+        neigbors.contains(&index)
     }
 
+    fn view_cell(&self, index: usize, cell: &Cell, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let game = self.displayed_game();
+        let value = cell.get_value_display_string();
+        let cell_is_shown = cell.is_shown();
+        let selected_index = self.displayed_selected_index();
+        let cell_at_selected_index_is_shown = selected_index.map_or(false, |selected_index| game.cell(selected_index).is_shown());
+        let cell_is_at_selected_index = selected_index == Some(index);
+        let cell_is_first_clicked_mine = game.first_clicked_mine_index().is_some() && index == game.first_clicked_mine_index().unwrap();
+        let state_is_chording = self.mouse_state.is_chording(game.settings().chord_setting(), cell_at_selected_index_is_shown) && self.neighbors_selected_cell(index);
 
-    fn calculate_neighbors(&self, index: usize) -> HashSet<usize> {
-        let (row, col) = self.get_row_col_from_index(index);
-        let mut neighbors: HashSet<usize> = HashSet::new();
-        let r = row as isize;
-        let c = col as isize;
+        let mine  = { if cell_is_shown && cell.is_mine() && (cell_is_at_selected_index || cell_is_first_clicked_mine) { "mine" } else { "" } };
+        let shown = { if !cell.is_flagged() && (cell_is_shown || cell_is_at_selected_index || state_is_chording) { "clicked" } else { "" } };
+        let hint  = match self.replay_player.as_ref().map_or(self.hint.as_ref(), |_| None) {
+            Some(hint) if hint.deduction().index() == index => { if hint.deduction().is_safe() { "hint-safe" } else { "hint-mine" } },
+            Some(hint) if hint.sources().contains(&index) => { "hint-source" },
+            _ => { "" },
+        };
+        let cursor = if self.cursor_index == Some(index) && self.replay_player.is_none() { "cursor" } else { "" };
+        let replayed = match &self.replay_player {
+            Some(player) if player.last_action().map(|action| action.index()) == Some(index) => { "replay-action" },
+            _ => { "" },
+        };
+
+        let probability = match (&self.probabilities, &self.replay_player) {
+            (Some(probabilities), None) => probabilities[index],
+            _ => None,
+        };
+        let overlay = match probability {
+            Some(probability) if game.is_active() && !cell.is_flagged() => html! {
+                <span class="probability" style={format!("background-color: rgba(255, 0, 0, {:.2})", probability * 0.6)}>
+                    { format!("{:.0}", probability * 100.0) }
+                </span>
+            },
+            _ => html! { {value} },
+        };
 
-        if let Some(n) = self.get_index_from_row_col(r - 1, c - 1)  { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r - 1, c)      { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r - 1, c + 1)  { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r, c - 1)      { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r, c + 1)      { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r + 1, c - 1)  { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r + 1, c)      { neighbors.insert(n); }
-        if let Some(n) = self.get_index_from_row_col(r + 1, c + 1)  { neighbors.insert(n); }
+        let onmousedown = link.callback(move |e: MouseEvent| Msg::MouseDown(index, e));
+        let onmouseup   = link.callback(move |e: MouseEvent| Msg::MouseUp(index, e));
+        let ontouchstart = link.callback(move |e: TouchEvent| Msg::TouchStart(index, e));
 
-        neighbors
-    }
+        let (tag, border) = cell_tag(game.settings().topology());
 
-    fn get_random_cell_index(&self) -> usize {
-        let mut rng = rand::thread_rng();
-        rng.gen_range(0..(self.settings.dimensions().width() * self.settings.dimensions().height()))
+        html! {
+            <@{tag} key={index}
+                class={classes!(border)} {onmousedown} {onmouseup} {ontouchstart}
+            >
+                <div class={classes!("cell", shown, mine, hint, replayed, cursor, cell.color().to_string())}>{overlay}</div>
+            </@>
+        }
     }
 
-    fn get_index_from_row_col(&self, row: isize, col: isize) -> Option<usize> {
-        if row >= 0 && (row as usize) < self.settings.dimensions().height() &&
-           col >= 0 && (col as usize) < self.settings.dimensions().width() {
-            Some((row as usize * self.settings.dimensions().width()) + col as usize)
+    // A ghost row repeats row `y` without any of its controls
+    fn view_row(&self, y: usize, ghost: bool, ctx: &Context<Self>) -> Html {
+        let game = self.displayed_game();
+        let settings = game.settings();
+        let width = settings.dimensions().width();
+        let index_offset = (self.displayed_layer() * settings.dimensions().layer_cell_count()) + (y * width);
+
+        let row_cells = (0..width).map(|x| {
+            let index = index_offset + x;
+            if ghost { self.view_ghost_cell(index, format!("ghost-{}", index)) } else { self.view_cell(index, game.cell(index), ctx) }
+        });
+        let ghost_left = settings.wraps_columns().then(|| self.view_ghost_cell(index_offset + width - 1, "ghost-left".into()));
+        let ghost_right = settings.wraps_columns().then(|| self.view_ghost_cell(index_offset, "ghost-right".into()));
+        let key = if ghost { format!("ghost-{}", y) } else { y.to_string() };
+
+        if settings.topology() == Topology::Hex {
+            html! {
+                <div {key} class={classes!("hex-row", (y % 2 == 1).then_some("hex-row-offset"))}>
+                    { for ghost_left }
+                    { for row_cells }
+                    { for ghost_right }
+                </div>
+            }
         } else {
-            None
+            html! {
+                <tr {key} class="game-row">
+                    { for ghost_left }
+                    { for row_cells }
+                    { for ghost_right }
+                </tr>
+            }
         }
     }
 
-    fn get_row_col_from_index(&self, index: usize) -> (usize, usize) {
-        let row = index / self.settings.dimensions().width();
-        let col = index % self.settings.dimensions().width();
-
-        (row, col)
-    }
-
-    fn neighbors_selected_cell(&self, index: usize) -> bool {
-        let Some(selected_index) = self.selected_cell_index else { return false; };
-        if index == selected_index { return true; }
-
-        let neigbors = &self.neighbors[selected_index];
-        neigbors.contains(&index)
-    }
+    fn view_ghost_cell(&self, index: usize, key: String) -> Html {
+        let game = self.displayed_game();
+        let cell = game.cell(index);
+        let shown = if cell.is_shown() { "clicked" } else { "" };
+        let (tag, border) = cell_tag(game.settings().topology());
 
-    fn click_neighboring_empty_cells(&mut self, index: usize) {
-        let neighbors = self.neighbors[index].clone();
-        for index in neighbors {
-            self.handle_click(index, None);
+        html! {
+            <@{tag} {key} class={classes!(border, "ghost")}>
+                <div class={classes!("cell", shown, cell.color().to_string())}>{ cell.get_value_display_string() }</div>
+            </@>
         }
     }
 
-    fn click_all_mines(&mut self, ctx: Option<&Context<Self>>) {
-        for i in 0..self.mine_indices.len() {
-            let index = self.mine_indices[i];
-            self.handle_click(index, ctx);
-        }
-    }
+    // A miniature of `layer` showing which of its cells touch the focused one; clicking it switches to that layer
+    fn view_layer_preview(&self, layer: usize, ctx: &Context<Self>) -> Html {
+        let game = self.displayed_game();
+        let dimensions = game.settings().dimensions();
+        let is_hex = game.settings().topology() == Topology::Hex;
+        let focus_neighbors = self.displayed_focus_index().map(|index| game.neighbors(index));
+
+        let rows = (0..dimensions.height()).map(|y| {
+            let cells = (0..dimensions.width()).map(|x| {
+                let index = (layer * dimensions.layer_cell_count()) + (y * dimensions.width()) + x;
+                let cell = game.cell(index);
+                let state = if cell.is_flagged() { "flagged" } else if cell.is_shown() { "clicked" } else { "" };
+                let neighbor = focus_neighbors.map_or(false, |neighbors| neighbors.contains(&index)).then_some("layer-neighbor");
+                html! { <div key={index} class={classes!("layer-preview-cell", state, neighbor)} /> }
+            });
+            html! {
+                <div key={y} class={classes!("layer-preview-row", (is_hex && y % 2 == 1).then_some("hex-row-offset"))}>
+                    { for cells }
+                </div>
+            }
+        });
 
-    fn count_flagged_mines(&self) -> usize {
-        self.cells.iter().filter(|cell| cell.is_flagged()).count()
+        html! {
+            <div class="layer-preview" title={format!("Show layer {}", layer + 1)} onclick={ctx.link().callback(move |_| Msg::ChangeLayer(layer))}>
+                <div class="layer-preview-title">{ format!("Layer {}", layer + 1) }</div>
+                { for rows }
+            </div>
+        }
     }
 
-    fn no_game_in_proggress(&self) -> bool {
-        self.interval.is_none()
+    // Only finished games have a summary, and not while a replay is being watched
+    fn summary(&self) -> Option<GameSummary> {
+        if self.game.is_active() || self.replay_player.is_some() { return None; }
+        let milliseconds = self.stopwatch.elapsed_milliseconds(now_milliseconds()) as u64;
+        Some(GameSummary::new(&self.game, self.clicks, milliseconds))
     }
 
-    fn view_cell(&self, index: usize, cell: &Cell, ctx: &Context<Self>) -> Html {
-        let link = ctx.link();
-        let value = cell.get_value_display_string();
-        let cell_is_shown = cell.is_shown();
-        let cell_at_selected_index_is_shown = self.selected_cell_index.is_some() && self.cells[self.selected_cell_index.unwrap()].is_shown();
-        let cell_is_at_selected_index = self.selected_cell_index.is_some() && index == self.selected_cell_index.unwrap();
-        let cell_is_first_clicked_mine = self.first_clicked_mine_index.is_some() && index == self.first_clicked_mine_index.unwrap();
-        let state_is_chording = self.mouse_state.is_chording(self.settings.chord_setting(), cell_at_selected_index_is_shown) && self.neighbors_selected_cell(index);
-
-        let mine  = { if cell_is_shown && cell.is_mine() && (cell_is_at_selected_index || cell_is_first_clicked_mine) { "mine" } else { "" } };
-        let shown = { if !cell.is_flagged() && (cell_is_shown || cell_is_at_selected_index || state_is_chording) { "clicked" } else { "" } };
-
-        let onmousedown = link.callback(move |e: MouseEvent| Msg::MouseDown(index, e));
-        let onmouseup   = link.callback(move |e: MouseEvent| Msg::MouseUp(index, e));
-
-        html! {
-            <td key={index}
-                class={classes!("cell-border")} {onmousedown} {onmouseup}
-            >
-                <div class={classes!("cell", shown, mine, cell.color().to_string())}>{value}</div>
-            </td>
+    // Once a game is over its replay is shared instead of its bare layout
+    fn share_code(&self) -> Option<String> {
+        match &self.replay {
+            Some(replay) if !self.game.is_active() => Some(replay.encode()),
+            _ => self.game.board_code().map(|board_code| board_code.encode()),
         }
     }
 
     fn check_difficulty_is_eq(&self, difficulty: Difficulty) -> bool {
         // https://stackoverflow.com/questions/32554285/compare-enums-only-by-variant-not-value
-        std::mem::discriminant(&self.settings.difficulty()) == std::mem::discriminant(&difficulty)
+        std::mem::discriminant(&self.game.settings().difficulty()) == std::mem::discriminant(&difficulty)
     }
 
     fn handle_change_size(&mut self, difficulty: Difficulty) -> bool {
-        self.settings.set_difficulty(difficulty);
-        self.cells = vec![Cell::new_empty(); self.settings.dimensions().width() * self.settings.dimensions().height()];
-        self.neighbors = vec![HashSet::new(); self.settings.dimensions().width() * self.settings.dimensions().height()];
-        self.mine_indices = vec![0; self.settings.dimensions().mines()];
-        self.handle_reset();
-        true
+        self.game.set_difficulty(difficulty);
+        self.storage.save(SETTINGS_KEY, &self.game.settings());
+        self.handle_reset()
+    }
+
+    fn handle_change_custom_dimensions(&mut self, dimensions: Dimensions) -> bool {
+        self.custom_dimensions = dimensions;
+        self.storage.save(CUSTOM_DIMENSIONS_KEY, &dimensions);
+        self.handle_change_size(Difficulty::Custom(dimensions))
     }
 
     fn handle_mouse_down(&mut self, index: usize, event: MouseEvent) -> bool {
-        if !self.active { return false; }
-        self.mouse_state = self.mouse_state.mouse_down(event);
+        if !self.game.is_active() || self.replay_player.is_some() { return false; }
+        self.mouse_state = self.mouse_state.mouse_down(event.button());
         self.face = Face::Nervous;
 
         match self.mouse_state {
@@ -221,13 +327,23 @@ impl App {
                 true
             },
             MouseState::Right => { self.handle_right_click(index) },
+            MouseState::Middle => {
+                if !self.mouse_state.is_chording(self.game.settings().chord_setting(), true) {
+                    self.face = face_for_state(self.game.state());
+                    return false;
+                }
+                // Stops the browser from starting to autoscroll
+                event.prevent_default();
+                self.selected_cell_index = Some(index);
+                true
+            },
             MouseState::AfterBoth | MouseState::Neither => { false }
         }
     }
 
-    fn handle_mouse_up(&mut self, index: usize, event: MouseEvent, ctx: Option<&Context<Self>>) -> bool {
-        if !self.active { return  false; }
-        let new_mouse_state = self.mouse_state.mouse_up(event);
+    fn handle_mouse_up(&mut self, index: usize, event: MouseEvent, ctx: &Context<Self>) -> bool {
+        if !self.game.is_active() || self.replay_player.is_some() { return  false; }
+        let new_mouse_state = self.mouse_state.mouse_up(event.button());
         match self.mouse_state {
             MouseState::AfterBoth | MouseState::Neither => {
                 self.mouse_state = new_mouse_state;
@@ -238,14 +354,13 @@ impl App {
                 let Some(selected_cell_index) = self.selected_cell_index else { return false; };
                 if selected_cell_index != index { return true; }
 
-                let chord_setting = self.settings.chord_setting();
-                let cell_is_shown = self.cells[index].is_shown();
+                let chord_setting = self.game.settings().chord_setting();
+                let cell_is_shown = self.game.cell(index).is_shown();
                 let is_chording = self.mouse_state.is_chording(chord_setting, cell_is_shown);
 
                 self.mouse_state = new_mouse_state;
                 if is_chording {
-                    self.handle_chord(index, ctx);
-                    true
+                    self.handle_chord(index)
                 } else {
                     self.handle_click(index, ctx)
                 }
@@ -255,11 +370,87 @@ impl App {
                 false
             },
             MouseState::Both => {
-                self.handle_chord(index, ctx);
+                self.handle_chord(index);
                 self.mouse_state = new_mouse_state;
                 true
-            }
+            },
+            MouseState::Middle => {
+                if !new_mouse_state.is_neither() { return false; }
+                let is_chording = self.mouse_state.is_chording(self.game.settings().chord_setting(), true);
+
+                self.mouse_state = new_mouse_state;
+                if is_chording { self.handle_chord(index) } else { false }
+            },
+        }
+    }
+
+    fn handle_key_down(&mut self, event: KeyboardEvent, ctx: &Context<Self>) -> bool {
+        if event.ctrl_key() || event.alt_key() || event.meta_key() { return false; }
+        let is_typing = event
+            .target()
+            .and_then(|target| target.dyn_into::<Element>().ok())
+            .map_or(false, |element| matches!(element.tag_name().as_str(), "INPUT" | "SELECT" | "TEXTAREA"));
+        if is_typing { return false; }
+        let Some(action) = self.key_bindings.action(&event.key()) else { return false; };
+        // Page Up and Page Down scroll the page as usual while there's only one layer
+        let changes_layer = matches!(action, KeyAction::PreviousLayer | KeyAction::NextLayer);
+        if changes_layer && !self.displayed_game().settings().dimensions().is_layered() { return false; }
+
+        event.prevent_default();
+        if action == KeyAction::Reset { return self.handle_reset(); }
+        if self.replay_player.is_some() { return false; }
+        // The first key press only shows the cursor, in the middle of the layer being shown
+        let Some(index) = self.cursor_index else {
+            let layer_cell_count = self.game.settings().dimensions().layer_cell_count();
+            self.cursor_index = Some((self.layer * layer_cell_count) + (layer_cell_count / 2));
+            return true;
+        };
+
+        match action {
+            KeyAction::Up => { self.move_cursor(index, 0, -1, 0) },
+            KeyAction::Down => { self.move_cursor(index, 0, 1, 0) },
+            KeyAction::Left => { self.move_cursor(index, 0, 0, -1) },
+            KeyAction::Right => { self.move_cursor(index, 0, 0, 1) },
+            KeyAction::PreviousLayer => { self.move_cursor(index, -1, 0, 0) },
+            KeyAction::NextLayer => { self.move_cursor(index, 1, 0, 0) },
+            KeyAction::Reveal => {
+                let chords_on_reveal = self.game.settings().chord_setting().chords_on_left_click();
+                if self.game.cell(index).is_shown() && chords_on_reveal {
+                    self.handle_chord(index)
+                } else {
+                    self.handle_click(index, ctx)
+                }
+            },
+            KeyAction::Flag => { self.handle_right_click(index) },
+            KeyAction::Chord => { self.handle_chord(index) },
+            KeyAction::Reset => { false },
+        }
+    }
+
+    // The cursor stops at the edges of the board, and the board follows it from layer to layer
+    fn move_cursor(&mut self, index: usize, layer_delta: isize, row_delta: isize, col_delta: isize) -> bool {
+        let layer = self.game.get_layer_from_index(index) as isize;
+        let (row, col) = self.game.get_row_col_from_index(index);
+        let Some(index) = self.game.get_index_from_layer_row_col(layer + layer_delta, row as isize + row_delta, col as isize + col_delta) else { return false; };
+        self.cursor_index = Some(index);
+        self.layer = self.game.get_layer_from_index(index);
+        true
+    }
+
+    // The cursor comes along to the same spot on the new layer
+    fn handle_change_layer(&mut self, layer: usize) -> bool {
+        if layer >= self.displayed_game().settings().dimensions().depth() { return false; }
+        if let Some(index) = self.cursor_index {
+            self.move_cursor(index, layer as isize - self.game.get_layer_from_index(index) as isize, 0, 0);
         }
+        self.layer = layer;
+        true
+    }
+
+    fn handle_change_key_bindings(&mut self, key_bindings: KeyBindings) -> bool {
+        key_bindings.save(&mut self.storage);
+        self.key_bindings = key_bindings;
+        true
     }
 
     fn handle_mouse_move(&mut self, event: MouseEvent) -> bool {
@@ -283,9 +474,74 @@ impl App {
         false
     }
 
+    fn handle_touch_start(&mut self, index: usize, event: TouchEvent, ctx: &Context<Self>) -> bool {
+        if !self.game.is_active() || self.replay_player.is_some() { return false; }
+        // A second finger means a pinch or a scroll, not a tap
+        let touches = event.touches();
+        if touches.length() != 1 { return self.handle_touch_cancel(); }
+        let Some(touch) = touches.get(0) else { return false; };
+
+        self.touch_press = Some(TouchPress::new(index, touch.client_x() as f64, touch.client_y() as f64));
+        let callback = ctx.link().callback(|_| Msg::LongPress);
+        self.long_press_timeout = Some(Timeout::new(LONG_PRESS_MILLISECONDS, move || callback.emit(())));
+        if self.input_mode == InputMode::Dig { self.selected_cell_index = Some(index); }
+        self.face = Face::Nervous;
+        true
+    }
+
+    fn handle_touch_move(&mut self, event: TouchEvent) -> bool {
+        let Some(press) = self.touch_press else { return false; };
+        let Some(touch) = event.touches().get(0) else { return false; };
+        if !press.is_drag(touch.client_x() as f64, touch.client_y() as f64) { return false; }
+
+        self.handle_touch_cancel()
+    }
+
+    fn handle_touch_end(&mut self, event: TouchEvent, ctx: &Context<Self>) -> bool {
+        // Stops the browser from following up with emulated mouse events
+        event.prevent_default();
+        let Some(press) = self.touch_press else { return false; };
+        self.handle_touch_cancel();
+        if press.is_long_pressed() { return true; }
+
+        self.handle_tap(press.index(), self.input_mode, ctx)
+    }
+
+    fn handle_touch_cancel(&mut self) -> bool {
+        self.touch_press = None;
+        self.long_press_timeout = None;
+        self.selected_cell_index = None;
+        self.face = face_for_state(self.game.state());
+        true
+    }
+
+    fn handle_long_press(&mut self, ctx: &Context<Self>) -> bool {
+        let Some(press) = &mut self.touch_press else { return false; };
+        if press.is_long_pressed() { return false; }
+
+        press.long_press();
+        let index = press.index();
+        self.long_press_timeout = None;
+        self.selected_cell_index = None;
+        self.face = face_for_state(self.game.state());
+        self.handle_tap(index, self.input_mode.toggled(), ctx)
+    }
+
+    // Tapping a shown number chords whatever the mode
+    fn handle_tap(&mut self, index: usize, input_mode: InputMode, ctx: &Context<Self>) -> bool {
+        if self.game.cell(index).is_shown() { return self.handle_chord(index); }
+
+        match input_mode {
+            InputMode::Dig => { self.handle_click(index, ctx) },
+            InputMode::Flag => { self.handle_right_click(index) },
+        }
+    }
+
     fn handle_tick(&mut self) -> bool {
-        self.seconds_played += 1;
-        if self.seconds_played > 999 { self.seconds_played = 999; }
+        let seconds_played = cmp::min(self.stopwatch.elapsed_milliseconds(now_milliseconds()) as usize / 1000, 999);
+        if seconds_played == self.seconds_played { return false; }
+
+        self.seconds_played = seconds_played;
         true
     }
 
@@ -293,119 +549,404 @@ impl App {
         self.interval = None;
         self.face = Face::Happy;
         self.seconds_played = 0;
-        self.shown_cells_count = 0;
-        self.clear_cells();
-        self.first_clicked_mine_index = None;
-        self.active = true;
+        self.stopwatch.reset();
+        self.replay = None;
+        self.handle_close_replay();
+        self.history.clear();
+        self.unranked = false;
+        self.chosen_board = false;
+        self.clicks = ClickCounts::default();
+        self.game.reset();
+        self.handle_board_changed();
+        self.save_game();
         true
     }
 
-    fn handle_click(&mut self, index: usize, ctx: Option<&Context<Self>>) -> bool {
-        if !self.active { return false; }
-
-        if self.no_game_in_proggress() {
-            self.reassign_cells(index);
-            self.reset_interval(ctx.unwrap());
+    fn handle_hint(&mut self) -> bool {
+        if self.game.settings().has_multi_mine_cells() {
+            self.hint_message = Some("Hints only work with one mine per cell.".into());
+            return true;
         }
-
-        if self.cells[index].is_shown() || self.cells[index].is_flagged() {
-            self.face = Face::Happy;
+        if !self.game.has_started() {
+            self.hint_message = Some("Click anywhere to start.".into());
             return true;
         }
+        if !self.game.is_active() { return false; }
+
+        self.hint = Solver::new(&self.game).hint(&self.game);
+        self.hint_message = Some(match &self.hint {
+            Some(hint) => {
+                // The deduction might be on another layer
+                self.layer = self.game.get_layer_from_index(hint.deduction().index());
+                hint.explanation(&self.game)
+            },
+            None => "Nothing can be deduced from here, you'll have to guess.".into(),
+        });
+        true
+    }
+
+    // Anything derived from what's on the board is stale now
+    fn handle_board_changed(&mut self) {
+        let cell_count = self.game.cells().len();
+        self.cursor_index = self.cursor_index.filter(|index| *index < cell_count);
+        self.layer = self.layer.min(self.game.settings().dimensions().depth() - 1);
+        self.hint = None;
+        self.hint_message = None;
+        if self.game.settings().has_multi_mine_cells() { self.probabilities = None; }
+        if self.probabilities.is_some() {
+            self.probabilities = Some(mine_probabilities(&self.game));
+        }
+    }
 
-        self.cells[index].handle_click();
+    // The odds assume one mine per cell, so they stay off otherwise
+    fn handle_toggle_probabilities(&mut self) -> bool {
+        self.probabilities = match self.probabilities {
+            Some(_) => None,
+            None if self.game.settings().has_multi_mine_cells() => None,
+            None => Some(mine_probabilities(&self.game)),
+        };
+        true
+    }
+
+    fn handle_change_generation(&mut self, generation_setting: GenerationSetting) -> bool {
+        let mut settings = self.game.settings();
+        settings.set_generation_setting(generation_setting);
+        self.handle_change_settings(settings)
+    }
 
-        if self.cells[index].is_mine() {
-            let Some(selected_index) = self.selected_cell_index else { return false; };
-            if self.first_clicked_mine_index.is_none() && (index == selected_index || self.neighbors[selected_index].contains(&index)) {
-                self.first_clicked_mine_index = Some(index);
-                self.click_all_mines(ctx);
-                self.active = false;
-                self.face = Face::Dead;
-                self.interval = None;
+    fn handle_change_settings(&mut self, mut settings: Settings) -> bool {
+        // A bigger first click safe zone can leave too little room for the custom mines
+        if let Difficulty::Custom(dimensions) = settings.difficulty() {
+            if let Err(DimensionsError::TooManyMines(max_mines)) = settings.validate_dimensions(dimensions.width(), dimensions.height(), dimensions.depth(), dimensions.mines()) {
+                self.custom_dimensions = Dimensions::new(dimensions.width(), dimensions.height(), max_mines).with_depth(dimensions.depth());
+                self.storage.save(CUSTOM_DIMENSIONS_KEY, &self.custom_dimensions);
+                settings.set_difficulty(Difficulty::Custom(self.custom_dimensions));
             }
+        }
+
+        // A new shape of board can't carry on the game that's being played
+        let reshaped = !settings.has_same_layout(&self.game.settings());
+        self.game.set_settings(settings);
+        self.storage.save(SETTINGS_KEY, &settings);
+        if reshaped { self.handle_reset(); } else { self.save_game(); }
+        true
+    }
+
+    // Boards picked by seed or code can be practised in advance, so they're kept out of the stats
+    fn handle_set_seed(&mut self, seed: u64) -> bool {
+        self.handle_reset();
+        self.chosen_board = true;
+        self.game.reset_with_seed(seed);
+        self.handle_board_changed();
+        true
+    }
+
+    fn handle_load_board(&mut self, code: String, ctx: &Context<Self>) -> bool {
+        if Replay::is_replay_code(&code) { return self.handle_load_replay(code); }
+
+        let board_code = match BoardCode::decode(&code) {
+            Ok(board_code) => board_code,
+            Err(error) => {
+                log::warn!("Couldn't load board {:?}: {}", code, error);
+                return true;
+            },
+        };
+
+        // Loaded into a copy, so a board that doesn't fit leaves the game being played alone
+        let mut game = self.game.clone();
+        if let Err(error) = game.load_board(&board_code) {
+            log::warn!("Couldn't load board {:?}: {}", code, error);
             return true;
         }
 
-        self.face = Face::Happy;
-        self.shown_cells_count += 1;
+        self.handle_reset();
+        self.chosen_board = true;
+        self.game = game;
+        // The code may have switched the grid, which the settings should remember
+        self.storage.save(SETTINGS_KEY, &self.game.settings());
+        if let Difficulty::Custom(dimensions) = self.game.settings().difficulty() {
+            self.custom_dimensions = dimensions;
+            self.storage.save(CUSTOM_DIMENSIONS_KEY, &dimensions);
+        }
+        self.handle_board_changed();
+        if let Some(index) = self.game.first_click_index() {
+            self.start_game(ctx);
+            self.record_action(ActionKind::Reveal, index);
+        }
+        self.handle_game_state();
+        true
+    }
 
-        // Recursively click all neighboring cells if we clicked a 0
-        if self.cells[index].is_zero() { self.click_neighboring_empty_cells(index); }
-        self.check_for_win();
+    fn handle_load_replay(&mut self, code: String) -> bool {
+        let player = Replay::decode(&code)
+            .map_err(|error| error.to_string())
+            .and_then(|replay| ReplayPlayer::new(replay).map_err(|error| error.to_string()));
+        match player {
+            Ok(player) => {
+                self.handle_close_replay();
+                self.replay_player = Some(player);
+            },
+            Err(error) => { log::warn!("Couldn't load replay {:?}: {}", code, error); },
+        }
         true
     }
 
-    fn handle_right_click(&mut self, index: usize) -> bool {
-        if !self.active { return false; }
+    fn handle_click(&mut self, index: usize, ctx: &Context<Self>) -> bool {
+        if !self.game.is_active() { return false; }
+
+        let before = self.practice_snapshot();
+        self.clicks.add_left();
+        let is_first_click = !self.game.has_started();
+        if self.game.reveal(index) {
+            if is_first_click { self.start_game(ctx); }
+            self.remember(before);
+            self.record_action(ActionKind::Reveal, index);
+            self.handle_board_changed();
+        }
+        self.handle_game_state();
+        true
+    }
 
-        self.cells[index].cycle_display(self.settings.allow_mark_cell_as_unknown());
+    fn handle_right_click(&mut self, index: usize) -> bool {
+        if !self.game.is_active() { return false; }
+
+        let before = self.practice_snapshot();
+        self.clicks.add_right();
+        if self.game.toggle_flag(index) {
+            self.remember(before);
+            self.record_action(ActionKind::for_marks_on(self.game.cell(index)), index);
+            self.handle_board_changed();
+            self.save_game();
+        }
         self.face = Face::Happy;
         true
     }
 
-    fn handle_chord(&mut self, index: usize, ctx: Option<&Context<Self>>) -> bool {
-        if !self.cells[index].is_shown() { return false; }
+    fn handle_chord(&mut self, index: usize) -> bool {
+        if self.game.settings().chord_setting_is_disabled() {
+            self.face = face_for_state(self.game.state());
+            return false;
+        }
+        let before = self.practice_snapshot();
+        if self.game.is_active() { self.clicks.add_chord(); }
+        if self.game.chord(index) {
+            self.remember(before);
+            self.record_action(ActionKind::Chord, index);
+            self.handle_board_changed();
+        }
+        self.handle_game_state();
+        true
+    }
+
+    // The first click isn't kept, since undoing it would mean laying out a new board
+    fn practice_snapshot(&self) -> Option<Snapshot> {
+        (self.practice && self.game.has_started()).then(|| Snapshot::new(self.game.position(), self.clicks))
+    }
+
+    fn remember(&mut self, before: Option<Snapshot>) {
+        if let Some(before) = before { self.history.record(before, &self.game); }
+    }
+
+    fn handle_toggle_practice(&mut self) -> bool {
+        self.practice = !self.practice;
+        if self.practice && self.game.has_started() { self.unranked = true; }
+        if !self.practice { self.history.clear(); }
+        self.save_game();
+        true
+    }
+
+    fn handle_undo(&mut self, ctx: &Context<Self>) -> bool {
+        if self.replay_player.is_some() { return false; }
+        if !self.history.undo(&mut self.game, &mut self.clicks) { return false; }
+        self.handle_rewound(ctx)
+    }
+
+    fn handle_redo(&mut self, ctx: &Context<Self>) -> bool {
+        if self.replay_player.is_some() { return false; }
+        if !self.history.redo(&mut self.game, &mut self.clicks) { return false; }
+        self.handle_rewound(ctx)
+    }
+
+    // A rewound game no longer matches its recording, so the replay is dropped
+    fn handle_rewound(&mut self, ctx: &Context<Self>) -> bool {
+        self.replay = None;
+        if self.game.is_active() && !self.stopwatch.is_running() {
+            self.stopwatch.resume(now_milliseconds());
+            self.reset_interval(ctx);
+        }
+        self.handle_board_changed();
+        self.handle_game_state();
+        true
+    }
+
+    // Called once the first click has laid out the board, so generation time isn't counted
+    fn start_game(&mut self, ctx: &Context<Self>) {
+        self.unranked = self.practice || self.chosen_board;
+        self.stopwatch.start(now_milliseconds());
+        self.reset_interval(ctx);
+        self.replay = self.game.board_code().map(|board_code| Replay::new(&board_code));
+    }
 
-        let neighbors = self.neighbors[index].clone();
-        let neighboring_mines = neighbors.iter().filter(|index| self.cells[**index].is_mine()).count();
-        let neighboring_flags = neighbors.iter().filter(|index| self.cells[**index].is_flagged()).count();
-        if neighboring_mines != neighboring_flags { return false; }
+    fn record_action(&mut self, kind: ActionKind, index: usize) {
+        let Some(replay) = &mut self.replay else { return; };
 
-        for index in neighbors {
-            self.handle_click(index, ctx);
+        let milliseconds = self.stopwatch.elapsed_milliseconds(now_milliseconds()) as u64;
+        replay.record(kind, index, milliseconds);
+    }
+
+    fn handle_open_replay(&mut self) -> bool {
+        let Some(replay) = &self.replay else { return false; };
+        if self.game.is_active() { return false; }
+
+        match ReplayPlayer::new(replay.clone()) {
+            Ok(player) => { self.replay_player = Some(player); },
+            Err(error) => { log::warn!("Couldn't open the replay: {}", error); },
         }
         true
     }
 
-    fn check_for_win(&mut self) {
-        if self.shown_cells_count + self.mine_indices.len() == self.cells.len() {
-            self.handle_win();
+    fn handle_close_replay(&mut self) -> bool {
+        self.replay_interval = None;
+        self.replay_player.take().is_some()
+    }
+
+    fn handle_replay_step(&mut self, forward: bool) -> bool {
+        let Some(player) = &mut self.replay_player else { return false; };
+
+        self.replay_interval = None;
+        if forward { player.step_forward(); } else { player.step_back(); }
+        true
+    }
+
+    fn handle_replay_toggle_play(&mut self, ctx: &Context<Self>) -> bool {
+        let Some(player) = &mut self.replay_player else { return false; };
+
+        if self.replay_interval.take().is_some() { return true; }
+        if player.is_finished() { player.seek(0); }
+        let callback = ctx.link().callback(|_| Msg::ReplayTick);
+        self.replay_interval = Some(Interval::new(REPLAY_TICK_MILLISECONDS as u32, move || callback.emit(())));
+        true
+    }
+
+    fn handle_replay_tick(&mut self) -> bool {
+        let Some(player) = &mut self.replay_player else { return false; };
+
+        player.advance(REPLAY_TICK_MILLISECONDS * self.replay_speed / 100);
+        if player.is_finished() { self.replay_interval = None; }
+        true
+    }
+
+    fn handle_game_state(&mut self) {
+        self.face = face_for_state(self.game.state());
+        if !self.game.is_active() && self.stopwatch.is_running() {
+            self.stopwatch.stop(now_milliseconds());
+            self.interval = None;
+            self.handle_tick();
+            self.record_stats();
         }
+        self.save_game();
     }
 
-    fn handle_win(&mut self) {
-        self.active = false;
-        self.face = Face::Cool;
-        self.flag_all_mines();
-        self.interval = None;
+    fn record_stats(&mut self) {
+        if self.unranked { return; }
+        let settings = self.game.settings();
+        if self.game.is_won() {
+            let milliseconds = self.stopwatch.elapsed_milliseconds(now_milliseconds()) as u64;
+            self.stats.record_win(&settings, milliseconds, js_sys::Date::now() as u64);
+        } else {
+            self.stats.record_loss(&settings);
+        }
+        self.stats.save(&mut self.storage);
     }
 
-    fn flag_all_mines(&mut self) {
-        for index in &self.mine_indices {
-            self.cells[*index].set_display_to_flagged();
+    // Only games in progress are kept, so a finished game is never restored or counted twice
+    fn save_game(&mut self) {
+        if !self.game.is_active() || !self.game.has_started() {
+            SavedGame::clear(&mut self.storage);
+            return;
         }
+
+        let elapsed_milliseconds = self.stopwatch.elapsed_milliseconds(now_milliseconds()) as u64;
+        SavedGame::new(self.game.clone(), elapsed_milliseconds, self.replay.clone(), self.unranked, self.clicks).save(&mut self.storage);
     }
 
+    fn restore_game(&mut self, saved_game: SavedGame, ctx: &Context<Self>) {
+        let (game, elapsed_milliseconds, replay, unranked, clicks) = saved_game.into_parts();
+        self.game = game;
+        self.game.set_clock(Clock(now_milliseconds));
+        self.replay = replay;
+        self.unranked = unranked;
+        self.clicks = clicks;
+        // The clock restarts with the page, so the start may land before zero
+        self.stopwatch.start(now_milliseconds() - elapsed_milliseconds as f64);
+        self.reset_interval(ctx);
+        self.handle_tick();
+        self.face = face_for_state(self.game.state());
+    }
 }
 
 impl Component for App {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
-        let settings = Settings::default();
-        let shown_cells_count   = 0;
-        let seconds_played      = 0;
-
-        let cells = vec![Cell::new_empty(); settings.dimensions().width() * settings.dimensions().height()];
-        let neighbors = vec![HashSet::new(); settings.dimensions().width() * settings.dimensions().height()];
-        let mines = vec![0; settings.dimensions().mines()];
-
-        Self {
-            active: true,
+    fn create(ctx: &Context<Self>) -> Self {
+        let storage = LocalStorage;
+        let mut game = Game::new(load_settings(&storage));
+        game.set_clock(Clock(now_milliseconds));
+        // Stored dimensions may predate the current limits, so check them again
+        let custom_dimensions = storage
+            .load::<Dimensions>(CUSTOM_DIMENSIONS_KEY)
+            .and_then(|dimensions| game.settings().validate_dimensions(dimensions.width(), dimensions.height(), dimensions.depth(), dimensions.mines()).ok())
+            .unwrap_or_default();
+        let on_pagehide = ctx.link().callback(|_| Msg::SaveGame);
+        let pagehide_listener = EventListener::new(&gloo::utils::window(), "pagehide", move |_| on_pagehide.emit(()));
+        let on_keydown = ctx.link().callback(Msg::KeyDown);
+        let keydown_listener = EventListener::new(&gloo::utils::document(), "keydown", move |event| {
+            if let Some(event) = event.dyn_ref::<KeyboardEvent>() { on_keydown.emit(event.clone()); }
+        });
+        let mut app = Self {
+            game,
             face: Face::Happy,
-            cells,
-            neighbors,
-            mine_indices: mines,
-            shown_cells_count,
-            seconds_played,
             selected_cell_index: None,
-            first_clicked_mine_index: None,
+            seconds_played: 0,
             mouse_state: MouseState::Neither,
-            settings,
             interval: None,
+            hint: None,
+            hint_message: None,
+            probabilities: None,
+            stopwatch: Stopwatch::default(),
+            replay: None,
+            replay_player: None,
+            replay_speed: 100,
+            replay_interval: None,
+            storage,
+            stats: Stats::load(&storage),
+            show_stats: false,
+            custom_dimensions,
+            show_settings: false,
+            key_bindings: KeyBindings::load(&storage),
+            cursor_index: None,
+            layer: 0,
+            input_mode: InputMode::default(),
+            touch_press: None,
+            long_press_timeout: None,
+            practice: false,
+            history: History::default(),
+            unranked: false,
+            chosen_board: false,
+            clicks: ClickCounts::default(),
+            _pagehide_listener: pagehide_listener,
+            _keydown_listener: keydown_listener,
+        };
+
+        if let Some(saved_game) = SavedGame::load(&app.storage) {
+            app.restore_game(saved_game, ctx);
+        }
+
+        if let Some(code) = board_code_from_url() {
+            ctx.link().send_message(Msg::LoadBoard(code));
         }
+        app
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Msg) -> bool {
@@ -413,11 +954,103 @@ impl Component for App {
             Msg::ChangeSize(difficulty) => {
                 self.handle_change_size(difficulty)
             },
+            Msg::ChangeCustomDimensions(dimensions) => {
+                self.handle_change_custom_dimensions(dimensions)
+            },
+            Msg::SetSeed(seed) => {
+                self.handle_set_seed(seed)
+            },
+            Msg::LoadBoard(code) => {
+                self.handle_load_board(code, ctx)
+            },
+            Msg::ChangeGeneration(generation_setting) => {
+                self.handle_change_generation(generation_setting)
+            },
+            Msg::Hint => {
+                self.handle_hint()
+            },
+            Msg::ToggleProbabilities => {
+                self.handle_toggle_probabilities()
+            },
+            Msg::OpenReplay => {
+                self.handle_open_replay()
+            },
+            Msg::CloseReplay => {
+                self.handle_close_replay()
+            },
+            Msg::ReplayStepBack => {
+                self.handle_replay_step(false)
+            },
+            Msg::ReplayStepForward => {
+                self.handle_replay_step(true)
+            },
+            Msg::ReplayTogglePlay => {
+                self.handle_replay_toggle_play(ctx)
+            },
+            Msg::ReplaySpeed(speed) => {
+                self.replay_speed = speed;
+                true
+            },
+            Msg::ReplayTick => {
+                self.handle_replay_tick()
+            },
+            Msg::ChangeSettings(settings) => {
+                self.handle_change_settings(settings)
+            },
+            Msg::ChangeKeyBindings(key_bindings) => {
+                self.handle_change_key_bindings(key_bindings)
+            },
+            Msg::ChangeLayer(layer) => {
+                self.handle_change_layer(layer)
+            },
+            Msg::ToggleStats => {
+                self.show_stats = !self.show_stats;
+                true
+            },
+            Msg::ToggleSettings => {
+                self.show_settings = !self.show_settings;
+                true
+            },
+            Msg::SaveGame => {
+                self.save_game();
+                false
+            },
             Msg::MouseDown(index, event) => {
                 self.handle_mouse_down(index, event)
             },
             Msg::MouseUp(index, event) => {
-                self.handle_mouse_up(index, event, Some(ctx))
+                self.handle_mouse_up(index, event, ctx)
+            },
+            Msg::KeyDown(event) => {
+                self.handle_key_down(event, ctx)
+            },
+            Msg::TouchStart(index, event) => {
+                self.handle_touch_start(index, event, ctx)
+            },
+            Msg::TouchMove(event) => {
+                self.handle_touch_move(event)
+            },
+            Msg::TouchEnd(event) => {
+                self.handle_touch_end(event, ctx)
+            },
+            Msg::TouchCancel => {
+                self.handle_touch_cancel()
+            },
+            Msg::LongPress => {
+                self.handle_long_press(ctx)
+            },
+            Msg::TogglePractice => {
+                self.handle_toggle_practice()
+            },
+            Msg::Undo => {
+                self.handle_undo(ctx)
+            },
+            Msg::Redo => {
+                self.handle_redo(ctx)
+            },
+            Msg::ToggleInputMode => {
+                self.input_mode = self.input_mode.toggled();
+                true
             },
             Msg::MouseMove(event) => {
                 self.handle_mouse_move(event)
@@ -434,31 +1067,39 @@ impl Component for App {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        let flagged_mines_count = self.count_flagged_mines() as isize;
-        let mines_remaining = cmp::max(self.mine_indices.len() as isize - flagged_mines_count, -99);
+        let game = self.displayed_game();
+        let flagged_mines_count = game.count_flagged_mines() as isize;
+        let mines_remaining = cmp::max(game.mines_count() as isize - flagged_mines_count, -99);
+        let (face, seconds_played) = match &self.replay_player {
+            Some(player) => (face_for_state(game.state()), cmp::min(player.milliseconds() / 1000, 999) as usize),
+            None => (self.face, self.seconds_played),
+        };
 
         let highlight_beginner = if self.check_difficulty_is_eq(Difficulty::Beginner) { "highlight" } else { "" };
         let highlight_intermediate = if self.check_difficulty_is_eq(Difficulty::Intermediate) { "highlight" } else { "" };
         let highlight_expert = if self.check_difficulty_is_eq(Difficulty::Expert) { "highlight" } else { "" };
         let highlight_custom = if self.check_difficulty_is_eq(Difficulty::Custom(Dimensions::new(0, 0, 0))) { "highlight" } else { "" }; // The specific dimensions don't matter here
-        let on_difficulty_selected = ctx.link().callback(|difficulty| Msg::ChangeSize(difficulty));
-
-        let cell_rows = self.cells
-            .chunks(self.settings.dimensions().width())
-            .enumerate()
-            .map(|(y, cells)| {
-                let index_offset = y * self.settings.dimensions().width();
-
-                let row_cells = cells
-                    .iter()
-                    .enumerate()
-                    .map(|(x, cell)| self.view_cell(index_offset + x, cell, ctx));
-                html! {
-                    <tr key={y} class="game-row">
-                        { for row_cells }
-                    </tr>
-                }
-            });
+        let on_difficulty_selected = ctx.link().callback(Msg::ChangeSize);
+        let no_guess = self.game.settings().generation_setting_is_no_guess();
+        let no_guess_title = if self.game.settings().has_multi_mine_cells() { "Needs one mine per cell" } else { "Only boards that can be solved without guessing" };
+        let on_no_guess_toggled = ctx.link().callback(move |_| {
+            Msg::ChangeGeneration(if no_guess { GenerationSetting::Random } else { GenerationSetting::NoGuess })
+        });
+
+        let is_hex = game.settings().topology() == Topology::Hex;
+        let height = game.settings().dimensions().height();
+        // Wrapped boards show the far edges faded out beyond each side
+        let ghost_top = game.settings().wraps_rows().then(|| self.view_row(height - 1, true, ctx));
+        let cell_rows = (0..height).map(|y| self.view_row(y, false, ctx));
+        let ghost_bottom = game.settings().wraps_rows().then(|| self.view_row(0, true, ctx));
+        let depth = game.settings().dimensions().depth();
+        let layer = self.displayed_layer();
+        let layer_buttons = (0..depth).map(|index| html! {
+            <a key={index} class={classes!("difficulty", (index == layer).then_some("highlight"))}
+                onclick={ctx.link().callback(move |_| Msg::ChangeLayer(index))}>{ index + 1 }</a>
+        });
+        let previous_layer = layer.checked_sub(1).map(|previous| self.view_layer_preview(previous, ctx));
+        let next_layer = (layer + 1 < depth).then(|| self.view_layer_preview(layer + 1, ctx));
 
         html! {
             <div class="container no-select">
@@ -466,37 +1107,184 @@ impl Component for App {
                     <DifficultyOption classes={highlight_beginner} difficulty={Difficulty::Beginner} on_difficulty_selected={&on_difficulty_selected} />
                     <DifficultyOption classes={highlight_intermediate} difficulty={Difficulty::Intermediate} on_difficulty_selected={&on_difficulty_selected} />
                     <DifficultyOption classes={highlight_expert} difficulty={Difficulty::Expert} on_difficulty_selected={&on_difficulty_selected} />
-                    <DifficultyOption classes={highlight_custom} difficulty={Difficulty::Custom(Dimensions::default())} on_difficulty_selected={&on_difficulty_selected} />
+                    <DifficultyOption classes={highlight_custom} difficulty={Difficulty::Custom(self.custom_dimensions)} on_difficulty_selected={&on_difficulty_selected} />
+                    <label class="difficulty" title={no_guess_title}>
+                        <input type="checkbox" checked={no_guess} disabled={self.game.settings().has_multi_mine_cells()} onchange={on_no_guess_toggled} />
+                        {"No guess"}
+                    </label>
+                    <a class="difficulty" onclick={ctx.link().callback(|_| Msg::Hint)}>{"Hint"}</a>
+                    <label class="difficulty">
+                        <input type="checkbox" checked={self.probabilities.is_some()} onchange={ctx.link().callback(|_| Msg::ToggleProbabilities)} />
+                        {"Odds"}
+                    </label>
+                    <label class="difficulty" title="Undo and redo moves; practice games aren't counted in the stats">
+                        <input type="checkbox" checked={self.practice} onchange={ctx.link().callback(|_| Msg::TogglePractice)} />
+                        {"Practice"}
+                    </label>
+                    if self.history.can_undo() && self.replay_player.is_none() {
+                        <a class="difficulty" onclick={ctx.link().callback(|_| Msg::Undo)}>{"Undo"}</a>
+                    }
+                    if self.history.can_redo() && self.replay_player.is_none() {
+                        <a class="difficulty" onclick={ctx.link().callback(|_| Msg::Redo)}>{"Redo"}</a>
+                    }
+                    <a class={classes!("difficulty", if self.input_mode == InputMode::Flag { "highlight" } else { "" })}
+                        title="What tapping a hidden cell does; holding it does the other"
+                        onclick={ctx.link().callback(|_| Msg::ToggleInputMode)}>{ format!("Tap: {}", self.input_mode.title()) }</a>
+                    <a class={classes!("difficulty", if self.show_stats { "highlight" } else { "" })} onclick={ctx.link().callback(|_| Msg::ToggleStats)}>{"Stats"}</a>
+                    <a class={classes!("difficulty", if self.show_settings { "highlight" } else { "" })} onclick={ctx.link().callback(|_| Msg::ToggleSettings)}>{"Settings"}</a>
+                    if self.replay.is_some() && !self.game.is_active() && self.replay_player.is_none() {
+                        <a class="difficulty" onclick={ctx.link().callback(|_| Msg::OpenReplay)}>{"Replay"}</a>
+                    }
                 </div>
 
+                if self.show_settings {
+                    <SettingsPanel
+                        settings={self.game.settings()}
+                        on_settings_changed={ctx.link().callback(Msg::ChangeSettings)}
+                        key_bindings={self.key_bindings.clone()}
+                        on_key_bindings_changed={ctx.link().callback(Msg::ChangeKeyBindings)}
+                    />
+                }
+
+                if self.check_difficulty_is_eq(Difficulty::Custom(self.custom_dimensions)) {
+                    <CustomDifficultyForm
+                        dimensions={self.custom_dimensions}
+                        settings={self.game.settings()}
+                        on_dimensions_entered={ctx.link().callback(Msg::ChangeCustomDimensions)}
+                    />
+                }
+
                 <div class="header">
                     <Counter value={mines_remaining} classes="left" />
                     <div id="resetButtonContainer" class="center">
-                        <span id="resetButton" onclick={ctx.link().callback(move |_| Msg::Reset)}>{ self.face.to_str() }</span>
+                        <span id="resetButton" onclick={ctx.link().callback(move |_| Msg::Reset)}>{ face.to_str() }</span>
                     </div>
-                    <Counter value={self.seconds_played as isize} classes="right" />
+                    <Counter value={seconds_played as isize} classes="right" />
                 </div>
 
+                if let Some(player) = &self.replay_player {
+                    <ReplayControls
+                        step={player.step()}
+                        steps={player.replay().actions().len()}
+                        playing={self.replay_interval.is_some()}
+                        speed={self.replay_speed}
+                        on_step_back={ctx.link().callback(|_| Msg::ReplayStepBack)}
+                        on_toggle_play={ctx.link().callback(|_| Msg::ReplayTogglePlay)}
+                        on_step_forward={ctx.link().callback(|_| Msg::ReplayStepForward)}
+                        on_speed_selected={ctx.link().callback(Msg::ReplaySpeed)}
+                        on_close={ctx.link().callback(|_| Msg::CloseReplay)}
+                    />
+                }
+
+                <SeedInput seed={self.game.seed()} on_seed_entered={ctx.link().callback(Msg::SetSeed)} />
+                <BoardCodeInput code={self.share_code()} on_code_entered={ctx.link().callback(Msg::LoadBoard)} />
+
+                if depth > 1 {
+                    <div class="settings layer-switcher">
+                        {"Layer"}
+                        { for layer_buttons }
+                    </div>
+                }
+
                 <div class="board-container">
-                    <table id="board" class="board"
+                    { for previous_layer }
+                    <@{if is_hex { "div" } else { "table" }} id="board" class={classes!("board", is_hex.then_some("hex-board"))}
                         oncontextmenu={ ctx.link().callback(move |e: MouseEvent| { e.prevent_default(); Msg::Ignore }) }
                         onmousemove={ ctx.link().callback(Msg::MouseMove)}
+                        ontouchmove={ ctx.link().callback(Msg::TouchMove) }
+                        ontouchend={ ctx.link().callback(Msg::TouchEnd) }
+                        ontouchcancel={ ctx.link().callback(|_| Msg::TouchCancel) }
                     >
+                        { for ghost_top }
                         { for cell_rows }
-                    </table>
+                        { for ghost_bottom }
+                    </@>
+                    { for next_layer }
                 </div>
+
+                if self.game.is_won() && self.replay_player.is_none() {
+                    <div class="result-message">
+                        { format!("Cleared in {:.3} seconds", self.stopwatch.elapsed_milliseconds(now_milliseconds()) / 1000.0) }
+                        if self.unranked { { if self.chosen_board { " (chosen board, not counted)" } else { " (practice, not counted)" } } }
+                    </div>
+                }
+
+                if let Some(summary) = self.summary() {
+                    <SummaryPanel {summary} />
+                }
+
+                if let Some(hint_message) = &self.hint_message {
+                    <div class="hint-message">{ hint_message }</div>
+                }
+
+                if self.show_stats {
+                    <StatsPanel
+                        title={stats_title(&self.game.settings())}
+                        stats={self.stats.for_settings(&self.game.settings())}
+                    />
+                }
             </div>
         }
     }
 
     fn rendered(&mut self, ctx: &Context<Self>, _: bool) {
-        if self.selected_cell_index.is_some() && self.mouse_state.is_neither() && self.active {
+        if self.selected_cell_index.is_some() && self.mouse_state.is_neither() && self.game.is_active() {
             self.selected_cell_index = None;
             ctx.link().callback(move |_| {Msg::ForceRender}).emit(());
         }
     }
 }
 
+fn face_for_state(state: GameState) -> Face {
+    match state {
+        GameState::Won => Face::Cool,
+        GameState::Lost => Face::Dead,
+        GameState::Ready | GameState::Playing => Face::Happy,
+    }
+}
+
+fn now_milliseconds() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map_or(0.0, |performance| performance.now())
+}
+
+/// The saved settings, or the defaults if they're missing or describe a board that's no longer allowed.
+fn load_settings(storage: &LocalStorage) -> Settings {
+    let Some(mut settings) = storage.load::<Settings>(SETTINGS_KEY) else { return Settings::default(); };
+    // Keeps the stored dimensions in step with the difficulty they came from
+    settings.set_difficulty(settings.difficulty());
+    settings.set_max_mines_per_cell(settings.max_mines_per_cell());
+    let dimensions = settings.dimensions();
+    if settings.validate_dimensions(dimensions.width(), dimensions.height(), dimensions.depth(), dimensions.mines()).is_err() { return Settings::default(); }
+    settings
+}
+
+// Hexagons are laid out in offset rows of divs rather than a table
+fn cell_tag(topology: Topology) -> (&'static str, &'static str) {
+    match topology {
+        Topology::Square => ("td", "cell-border"),
+        Topology::Hex => ("div", "hex-cell"),
+    }
+}
+
+fn stats_title(settings: &Settings) -> String {
+    match settings.variant() {
+        Some(variant) => format!("{} ({})", settings.difficulty().title(), variant),
+        None => settings.difficulty().title(),
+    }
+}
+
+fn board_code_from_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    search
+        .trim_start_matches('?')
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("board="))
+        .map(String::from)
+}
+
 fn main() {
+    wasm_logger::init(wasm_logger::Config::default());
     yew::start_app::<App>();
 }
\ No newline at end of file
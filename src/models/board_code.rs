@@ -0,0 +1,411 @@
+use crate::models::neighborhood::Neighborhood;
+use crate::models::settings::{Dimensions, Settings, MAX_MINES_PER_CELL};
+use crate::models::topology::Topology;
+use std::fmt;
+
+// URL-safe base64 alphabet, so a code can go straight into a query string
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BITS_PER_CHAR: usize = 6;
+// Two bits for each mined cell's extra mines, so three cells to a character
+const BITS_PER_COUNT: usize = 2;
+// Joins the size to the names of any rules that change which cells are neighbors
+const LAYOUT_SEPARATOR: char = '-';
+const HEX_NAME: &str = "hex";
+const TORUS_NAME: &str = "torus";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BoardCodeError {
+    MissingSection,
+    InvalidDimensions,
+    InvalidLayout,
+    InvalidFirstClick,
+    InvalidMines,
+    InvalidActions,
+}
+
+impl fmt::Display for BoardCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BoardCodeError::MissingSection      => write!(f, "board code should look like WxH.FIRST_CLICK.MINES"),
+            BoardCodeError::InvalidDimensions   => write!(f, "board code has invalid dimensions"),
+            BoardCodeError::InvalidLayout       => write!(f, "board code has an unknown grid"),
+            BoardCodeError::InvalidFirstClick   => write!(f, "board code has an invalid first click"),
+            BoardCodeError::InvalidMines        => write!(f, "board code has an invalid mine layout"),
+            BoardCodeError::InvalidActions      => write!(f, "replay code has an invalid action"),
+        }
+    }
+}
+
+impl std::error::Error for BoardCodeError {}
+
+/// A complete mine layout, encoded as `WxH.FIRST_CLICK.MINES`, or `WxHxD.FIRST_CLICK.MINES` for a layered board.
+///
+/// A hexagonal board adds `-hex` to its size, a square one with other neighbors than the usual
+/// eight adds the neighborhood's name (such as `-knight`), and one with wrapped edges adds
+/// `-torus`, so the layout is read with the same neighbors it was played with whatever the
+/// reader's own settings are. `FIRST_CLICK` is empty when the layout was shared before anyone clicked, and `MINES` is a
+/// bitfield over the cell indices written six bits per character. Boards with several mines to a
+/// cell add a fourth `.COUNTS` section holding each mined cell's extra mines, two bits apiece.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BoardCode {
+    dimensions: Dimensions,
+    mine_indices: Vec<usize>,
+    first_click_index: Option<usize>,
+    topology: Topology,
+    wrap_edges: bool,
+    /// Always the classic neighborhood on hexagons, which only ever count the six around them.
+    neighborhood: Neighborhood,
+}
+
+impl BoardCode {
+    /// Only the size of `dimensions` is kept; the mine count comes from `mine_indices`, where a
+    /// cell holding several mines is listed once for each.
+    pub fn new(dimensions: Dimensions, mine_indices: Vec<usize>, first_click_index: Option<usize>) -> Self {
+        let mut mine_indices = mine_indices;
+        mine_indices.sort_unstable();
+        let dimensions = Dimensions::new(dimensions.width(), dimensions.height(), mine_indices.len()).with_depth(dimensions.depth());
+
+        BoardCode { dimensions, mine_indices, first_click_index, topology: Topology::default(), wrap_edges: false, neighborhood: Neighborhood::default() }
+    }
+
+    pub fn with_topology(mut self, topology: Topology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_wrap_edges(mut self, wrap_edges: bool) -> Self {
+        self.wrap_edges = wrap_edges;
+        self
+    }
+
+    pub fn with_neighborhood(mut self, neighborhood: Neighborhood) -> Self {
+        self.neighborhood = neighborhood;
+        self
+    }
+
+    /// The same layout with the first click left out, as shared before anyone clicked.
+    pub fn without_first_click(mut self) -> Self {
+        self.first_click_index = None;
+        self
+    }
+
+    pub fn dimensions(&self) -> Dimensions {
+        self.dimensions
+    }
+
+    pub fn mine_indices(&self) -> &[usize] {
+        &self.mine_indices
+    }
+
+    pub fn first_click_index(&self) -> Option<usize> {
+        self.first_click_index
+    }
+
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn wrap_edges(&self) -> bool {
+        self.wrap_edges
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// Switches `settings` to the grid this layout was laid out on, leaving every other rule alone.
+    pub fn apply_layout(&self, settings: &mut Settings) {
+        settings.set_topology(self.topology);
+        settings.set_wrap_edges(self.wrap_edges);
+        if self.topology == Topology::Square { settings.set_neighborhood(self.neighborhood); }
+        settings.set_max_mines_per_cell(settings.max_mines_per_cell().max(self.max_mines_per_cell()));
+    }
+
+    /// The most mines any one cell holds.
+    pub fn max_mines_per_cell(&self) -> usize {
+        self.mine_counts().iter().map(|(_, count)| *count).max().unwrap_or(1)
+    }
+
+    pub fn encode(&self) -> String {
+        let cell_count = self.dimensions.cell_count();
+        let mut bits = vec![false; cell_count];
+        for index in &self.mine_indices {
+            bits[*index] = true;
+        }
+
+        let mines: String = bits
+            .chunks(BITS_PER_CHAR)
+            .map(|chunk| {
+                let value = chunk.iter().enumerate().fold(0, |acc, (i, bit)| {
+                    if *bit { acc | (1 << (BITS_PER_CHAR - 1 - i)) } else { acc }
+                });
+                ALPHABET[value] as char
+            })
+            .collect();
+        let counts: String = if self.max_mines_per_cell() > 1 {
+            let extra_mines: Vec<usize> = self.mine_counts().iter().map(|(_, count)| count - 1).collect();
+            let counts: String = extra_mines
+                .chunks(BITS_PER_CHAR / BITS_PER_COUNT)
+                .map(|chunk| {
+                    let value = chunk.iter().enumerate().fold(0, |acc, (i, extra)| {
+                        acc | (extra << (BITS_PER_CHAR - (BITS_PER_COUNT * (i + 1))))
+                    });
+                    ALPHABET[value] as char
+                })
+                .collect();
+            format!(".{}", counts)
+        } else {
+            String::new()
+        };
+        let first_click = self.first_click_index.map(|index| index.to_string()).unwrap_or_default();
+
+        // Layered boards add their depth as a third side
+        let mut size = if self.dimensions.is_layered() {
+            format!("{}x{}x{}", self.dimensions.width(), self.dimensions.height(), self.dimensions.depth())
+        } else {
+            format!("{}x{}", self.dimensions.width(), self.dimensions.height())
+        };
+        for name in self.layout_names() {
+            size.push(LAYOUT_SEPARATOR);
+            size.push_str(&name);
+        }
+        format!("{}.{}.{}{}", size, first_click, mines, counts)
+    }
+
+    pub fn decode(code: &str) -> Result<Self, BoardCodeError> {
+        let mut sections = code.trim().split('.');
+        let (Some(size), Some(first_click), Some(mines), counts, None) = (sections.next(), sections.next(), sections.next(), sections.next(), sections.next()) else {
+            return Err(BoardCodeError::MissingSection);
+        };
+
+        let mut layout = size.split(LAYOUT_SEPARATOR);
+        let size = layout.next().unwrap_or_default();
+        let names: Vec<&str> = layout.collect();
+        let mut topology = Topology::default();
+        let mut wrap_edges = false;
+        let mut neighborhood = Neighborhood::default();
+        for name in &names {
+            match *name {
+                HEX_NAME => { topology = Topology::Hex; },
+                TORUS_NAME => { wrap_edges = true; },
+                name => { neighborhood = Neighborhood::from_name(name).ok_or(BoardCodeError::InvalidLayout)?; },
+            }
+        }
+
+        let sides = size
+            .split('x')
+            .map(|side| side.parse::<usize>().map_err(|_| BoardCodeError::InvalidDimensions))
+            .collect::<Result<Vec<usize>, BoardCodeError>>()?;
+        let (width, height, depth) = match sides[..] {
+            [width, height] => (width, height, 1),
+            [width, height, depth] => (width, height, depth),
+            _ => return Err(BoardCodeError::InvalidDimensions),
+        };
+        let dimensions = Dimensions::new(width, height, 0).with_depth(depth);
+        if width == 0 || height == 0 || depth == 0 || dimensions.width() != width || dimensions.height() != height || dimensions.depth() != depth {
+            return Err(BoardCodeError::InvalidDimensions);
+        }
+        let cell_count = dimensions.cell_count();
+
+        let first_click_index = if first_click.is_empty() {
+            None
+        } else {
+            let index: usize = first_click.parse().map_err(|_| BoardCodeError::InvalidFirstClick)?;
+            if index >= cell_count { return Err(BoardCodeError::InvalidFirstClick); }
+            Some(index)
+        };
+
+        if mines.len() != (cell_count + BITS_PER_CHAR - 1) / BITS_PER_CHAR { return Err(BoardCodeError::InvalidMines); }
+        let mut mine_indices = Vec::new();
+        for (char_index, c) in mines.bytes().enumerate() {
+            let value = ALPHABET.iter().position(|a| *a == c).ok_or(BoardCodeError::InvalidMines)?;
+            for bit in 0..BITS_PER_CHAR {
+                if value & (1 << (BITS_PER_CHAR - 1 - bit)) == 0 { continue; }
+                let index = char_index * BITS_PER_CHAR + bit;
+                if index >= cell_count { return Err(BoardCodeError::InvalidMines); }
+                mine_indices.push(index);
+            }
+        }
+        if let Some(counts) = counts {
+            let mined_cells = mine_indices.len();
+            mine_indices = BoardCode::decode_counts(counts, &mine_indices)?;
+            // Counts are only written when some cell holds more than one mine, so each board keeps a single code
+            if mine_indices.len() == mined_cells { return Err(BoardCodeError::InvalidMines); }
+        }
+        let dimensions = Dimensions::new(width, height, mine_indices.len()).with_depth(depth);
+        if dimensions.mines() != mine_indices.len() { return Err(BoardCodeError::InvalidMines); }
+
+        let board_code = BoardCode { dimensions, mine_indices, first_click_index, topology, wrap_edges, neighborhood };
+        // Each code has one spelling, so repeated or reordered names are turned away
+        if board_code.layout_names() != names { return Err(BoardCodeError::InvalidLayout); }
+        Ok(board_code)
+    }
+
+    // Private methods
+    // Only what differs from the classic grid is written, so classic codes stay as they were
+    fn layout_names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if self.topology == Topology::Hex { names.push(HEX_NAME.to_string()); }
+        if let Some(name) = self.neighborhood.name().filter(|_| self.topology == Topology::Square) { names.push(name); }
+        if self.wrap_edges { names.push(TORUS_NAME.to_string()); }
+        names
+    }
+
+    // Each mined cell alongside how many mines it holds, in index order
+    fn mine_counts(&self) -> Vec<(usize, usize)> {
+        let mut counts: Vec<(usize, usize)> = Vec::new();
+        for index in &self.mine_indices {
+            match counts.last_mut() {
+                Some((last, count)) if last == index => { *count += 1; },
+                _ => { counts.push((*index, 1)); },
+            }
+        }
+        counts
+    }
+
+    // Repeats each of `mined_cells` by the count the `COUNTS` section gives it
+    fn decode_counts(counts: &str, mined_cells: &[usize]) -> Result<Vec<usize>, BoardCodeError> {
+        let counts_per_char = BITS_PER_CHAR / BITS_PER_COUNT;
+        if counts.len() != (mined_cells.len() + counts_per_char - 1) / counts_per_char { return Err(BoardCodeError::InvalidMines); }
+
+        let mut mine_indices = Vec::new();
+        for (char_index, c) in counts.bytes().enumerate() {
+            let value = ALPHABET.iter().position(|a| *a == c).ok_or(BoardCodeError::InvalidMines)?;
+            for slot in 0..counts_per_char {
+                let extra = (value >> (BITS_PER_CHAR - (BITS_PER_COUNT * (slot + 1)))) & ((1 << BITS_PER_COUNT) - 1);
+                let Some(index) = mined_cells.get(char_index * counts_per_char + slot) else {
+                    if extra != 0 { return Err(BoardCodeError::InvalidMines); }
+                    continue;
+                };
+                if extra + 1 > MAX_MINES_PER_CELL { return Err(BoardCodeError::InvalidMines); }
+                mine_indices.extend(std::iter::repeat(*index).take(extra + 1));
+            }
+        }
+        Ok(mine_indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::neighborhood::NeighborMask;
+
+    fn round_trip(board_code: &BoardCode) -> BoardCode {
+        BoardCode::decode(&board_code.encode()).expect("an encoded board decodes")
+    }
+
+    #[test]
+    fn round_trips_a_board() {
+        let board_code = BoardCode::new(Dimensions::new(9, 9, 0), vec![80, 0, 13, 42], Some(40));
+
+        assert_eq!(round_trip(&board_code), board_code);
+        assert_eq!(board_code.mine_indices(), &[0, 13, 42, 80]);
+        assert_eq!(board_code.dimensions().mines(), 4);
+    }
+
+    #[test]
+    fn round_trips_a_board_before_the_first_click() {
+        let board_code = BoardCode::new(Dimensions::new(30, 16, 0), vec![5, 479], None);
+
+        assert!(board_code.encode().starts_with("30x16.."));
+        assert_eq!(round_trip(&board_code), board_code);
+    }
+
+    #[test]
+    fn round_trips_a_hex_board() {
+        let board_code = BoardCode::new(Dimensions::new(5, 4, 0), vec![2, 11], Some(0)).with_topology(Topology::Hex);
+
+        assert!(board_code.encode().starts_with("5x4-hex.0."));
+        assert_eq!(round_trip(&board_code), board_code);
+    }
+
+    #[test]
+    fn round_trips_a_wrapped_board() {
+        let board_code = BoardCode::new(Dimensions::new(6, 4, 0), vec![0, 23], None).with_topology(Topology::Hex).with_wrap_edges(true);
+
+        assert!(board_code.encode().starts_with("6x4-hex-torus.."));
+        assert_eq!(round_trip(&board_code), board_code);
+    }
+
+    #[test]
+    fn round_trips_a_board_with_other_neighbors() {
+        let knight = BoardCode::new(Dimensions::new(5, 5, 0), vec![7], None).with_neighborhood(Neighborhood::Knight).with_wrap_edges(true);
+        let mask = NeighborMask::from_offsets(&[(-2, 0), (0, 1)]);
+        let custom = BoardCode::new(Dimensions::new(5, 5, 0), vec![7], None).with_neighborhood(Neighborhood::Custom(mask));
+
+        assert!(knight.encode().starts_with("5x5-knight-torus.."));
+        assert_eq!(round_trip(&knight), knight);
+        assert_eq!(round_trip(&custom), custom);
+    }
+
+    #[test]
+    fn rejects_unknown_grids() {
+        for size in ["3x3-", "3x3-square", "3x3-hex-hex", "3x3-torus-hex", "hex-3x3", "3x3-hex-knight", "3x3-cross-knight", "3x3-mask0", "3x3-mask1000", "3x3-mask2000000", "3x3-mask0A"] {
+            assert_eq!(BoardCode::decode(&format!("{}..AA", size)), Err(BoardCodeError::InvalidLayout), "{}", size);
+        }
+    }
+
+    #[test]
+    fn round_trips_a_layered_board() {
+        let board_code = BoardCode::new(Dimensions::new(4, 3, 0).with_depth(3), vec![0, 12, 35], Some(20));
+
+        assert!(board_code.encode().starts_with("4x3x3."));
+        assert_eq!(round_trip(&board_code), board_code);
+    }
+
+    #[test]
+    fn round_trips_cells_with_several_mines() {
+        let board_code = BoardCode::new(Dimensions::new(5, 5, 0), vec![3, 3, 3, 7, 24, 24], None);
+
+        assert_eq!(board_code.max_mines_per_cell(), 3);
+        assert_eq!(board_code.encode().split('.').count(), 4);
+        assert_eq!(round_trip(&board_code), board_code);
+    }
+
+    #[test]
+    fn leaves_out_the_counts_for_one_mine_per_cell() {
+        let board_code = BoardCode::new(Dimensions::new(5, 5, 0), vec![3, 7], None);
+
+        assert_eq!(board_code.encode().split('.').count(), 3);
+    }
+
+    #[test]
+    fn rejects_missing_sections() {
+        assert_eq!(BoardCode::decode("9x9"), Err(BoardCodeError::MissingSection));
+        assert_eq!(BoardCode::decode("9x9.4"), Err(BoardCodeError::MissingSection));
+        assert_eq!(BoardCode::decode("3x3..AA.A.A"), Err(BoardCodeError::MissingSection));
+    }
+
+    #[test]
+    fn rejects_invalid_dimensions() {
+        for size in ["0x3", "3x0", "3x3x0", "3", "3x3x3x3", "ax3", "40x3", "3x3x9"] {
+            assert_eq!(BoardCode::decode(&format!("{}..A", size)), Err(BoardCodeError::InvalidDimensions), "{}", size);
+        }
+    }
+
+    #[test]
+    fn rejects_a_first_click_off_the_board() {
+        assert_eq!(BoardCode::decode("3x3.9.AA"), Err(BoardCodeError::InvalidFirstClick));
+        assert_eq!(BoardCode::decode("3x3.x.AA"), Err(BoardCodeError::InvalidFirstClick));
+    }
+
+    #[test]
+    fn rejects_invalid_mines() {
+        // Too short, too long, outside the alphabet, and a mine past the last of the nine cells
+        for mines in ["A", "AAA", "A*", "AB"] {
+            assert_eq!(BoardCode::decode(&format!("3x3..{}", mines)), Err(BoardCodeError::InvalidMines), "{}", mines);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_counts() {
+        // One mined cell needs exactly one character of counts, holding at most two extra mines
+        assert_eq!(BoardCode::decode("3x3..gA.AA"), Err(BoardCodeError::InvalidMines));
+        assert_eq!(BoardCode::decode("3x3..gA.w"), Err(BoardCodeError::InvalidMines));
+        assert_eq!(BoardCode::decode("3x3..gA.B"), Err(BoardCodeError::InvalidMines));
+        assert_eq!(BoardCode::decode("3x3..gA.g").map(|board_code| board_code.mine_indices().to_vec()), Ok(vec![0, 0, 0]));
+        // Counts that add no mines only spell a board that already has a shorter code
+        assert_eq!(BoardCode::decode("3x3..gA.A"), Err(BoardCodeError::InvalidMines));
+        assert_eq!(BoardCode::decode("3x3..AA."), Err(BoardCodeError::InvalidMines));
+    }
+}
@@ -1,8 +1,16 @@
+use crate::models::settings::MAX_MINES_PER_CELL;
+use serde::{Deserialize, Serialize};
+
 // use gloo_console as console;
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+/// The most mines a cell can count, beyond even 74 neighbors of a radius 2 cell on a layered board holding 3 mines each.
+pub const MAX_NUMBER: usize = u8::MAX as usize;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Value {
     Mine,
+    /// A cell holding two or more mines, when the settings allow it.
+    Mines(u8),
     Zero,
     One,
     Two,
@@ -12,12 +20,51 @@ pub enum Value {
     Six,
     Seven,
     Eight,
+    /// Nine or more, which only neighborhoods of more than 8 cells can have.
+    Many(u8),
 }
 
 impl Value {
+    /// The number of neighboring mines, or `None` for a mine.
+    pub fn number(&self) -> Option<usize> {
+        match self {
+            Value::Mine     => None,
+            Value::Mines(_) => None,
+            Value::Zero     => Some(0),
+            Value::One      => Some(1),
+            Value::Two      => Some(2),
+            Value::Three    => Some(3),
+            Value::Four     => Some(4),
+            Value::Five     => Some(5),
+            Value::Six      => Some(6),
+            Value::Seven    => Some(7),
+            Value::Eight    => Some(8),
+            Value::Many(n)  => Some(*n as usize),
+        }
+    }
+
+    /// How many mines the cell holds.
+    pub fn mines(&self) -> usize {
+        match self {
+            Value::Mine     => 1,
+            Value::Mines(n) => *n as usize,
+            _               => 0,
+        }
+    }
+
+    /// Whether this is the one way of writing its value, so `Mines` holds more than one mine and `Many` more than eight.
+    pub fn is_canonical(&self) -> bool {
+        match self {
+            Value::Mines(n) => { (2..=MAX_MINES_PER_CELL).contains(&(*n as usize)) },
+            Value::Many(n)  => { *n > 8 },
+            _               => { true },
+        }
+    }
+
     pub fn get_name_string(&self) -> String {
         match self {
             Value::Mine     => String::from(""),
+            Value::Mines(_) => String::from(""),
             Value::Zero     => String::from(""),
             Value::One      => String::from("one"),
             Value::Two      => String::from("two"),
@@ -27,27 +74,41 @@ impl Value {
             Value::Six      => String::from("six"),
             Value::Seven    => String::from("seven"),
             Value::Eight    => String::from("eight"),
+            Value::Many(_)  => String::from("many"),
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum DisplayState {
     Default,
     Unknown,
     Flagged,
+    /// Two or more flags on one cell, for boards whose cells can hold several mines.
+    Flags(u8),
     Shown(Value),
 }
 
 impl DisplayState {
-    pub fn get_display_string(&self) -> &str {
+    /// Whether this is the one way of writing it, so `Flags` holds more than one flag.
+    pub fn is_canonical(&self) -> bool {
         match self {
+            DisplayState::Flags(n) => { (2..=MAX_MINES_PER_CELL).contains(&(*n as usize)) },
+            DisplayState::Shown(value) => { value.is_canonical() },
+            _ => { true },
+        }
+    }
+
+    pub fn get_display_string(&self) -> String {
+        let display_string = match self {
             DisplayState::Default => " ",
             DisplayState::Flagged => "🚩",
+            DisplayState::Flags(n) => { return format!("🚩{}", n); },
             DisplayState::Unknown => "?",
             DisplayState::Shown(value) => {
                 match value {
                     Value::Mine     => "*",
+                    Value::Mines(n) => return format!("*{}", n),
                     Value::Zero     => " ",
                     Value::One      => "1",
                     Value::Two      => "2",
@@ -57,19 +118,42 @@ impl DisplayState {
                     Value::Six      => "6",
                     Value::Seven    => "7",
                     Value::Eight    => "8",
+                    Value::Many(n)  => return n.to_string(),
                 }
             }
-        }
+        };
+        display_string.to_string()
     }
 }
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+/// The color is left out when reading, since it's worked out again from the value.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "CellData")]
 pub struct Cell {
     value: Value,
     display: DisplayState,
     color: String,
 }
 
+#[derive(Deserialize)]
+struct CellData {
+    value: Value,
+    display: DisplayState,
+}
+
+impl TryFrom<CellData> for Cell {
+    type Error = &'static str;
+
+    fn try_from(data: CellData) -> Result<Self, Self::Error> {
+        if !data.value.is_canonical() || !data.display.is_canonical() { return Err("a cell has a count that another variant stands for"); }
+        if matches!(data.display, DisplayState::Shown(value) if value != data.value) { return Err("a cell shows a different value from its own"); }
+
+        let mut cell = Cell { value: data.value, display: DisplayState::Default, color: String::new() };
+        cell.set_display(data.display);
+        Ok(cell)
+    }
+}
+
 impl Cell {
     pub fn new(neighboring_mines: Option<usize>) -> Self {
         let value = Cell::calculate_value(neighboring_mines);
@@ -81,10 +165,26 @@ impl Cell {
         }
     }
 
+    /// A cell holding `mines` mines, which must be at least 1.
+    pub fn new_mines(mines: usize) -> Self {
+        let mut cell = Cell::new(None);
+        if mines > 1 { cell.set_value(Value::Mines(mines as u8)); }
+        cell
+    }
+
     pub fn new_empty() -> Self {
         Cell::new(Some(0))
     }
 
+    pub fn value(&self) -> Value {
+        self.value
+    }
+
+    /// The number on this cell if the player can see it.
+    pub fn shown_number(&self) -> Option<usize> {
+        if self.is_shown() { self.value.number() } else { None }
+    }
+
     pub fn color(&self) -> &str {
         &self.color
     }
@@ -96,17 +196,21 @@ impl Cell {
 
     pub fn handle_click(&mut self) {
         match self.display {
-            DisplayState::Flagged | DisplayState::Shown(_) => {},
+            DisplayState::Flagged | DisplayState::Flags(_) | DisplayState::Shown(_) => {},
             DisplayState::Default | DisplayState::Unknown => {
                 self.set_display(DisplayState::Shown(self.value));
             },
         }
     }
 
-    pub fn cycle_display(&mut self, allow_unknown: bool) {
+    /// Steps through one flag, then more up to `max_flags`, then the unknown mark if it's allowed, then back to nothing.
+    pub fn cycle_display(&mut self, allow_unknown: bool, max_flags: usize) {
         match self.display {
             DisplayState::Default => { self.set_display(DisplayState::Flagged) }
-            DisplayState::Flagged => {
+            DisplayState::Flagged | DisplayState::Flags(_) if self.flag_count() < max_flags => {
+                self.set_display_to_flags(self.flag_count() + 1)
+            }
+            DisplayState::Flagged | DisplayState::Flags(_) => {
                 if allow_unknown {
                     self.set_display(DisplayState::Unknown)
                 } else {
@@ -123,11 +227,27 @@ impl Cell {
     }
 
     pub fn is_mine(&self) -> bool {
-        self.value == Value::Mine
+        matches!(self.value, Value::Mine | Value::Mines(_))
+    }
+
+    pub fn mine_count(&self) -> usize {
+        self.value.mines()
     }
 
     pub fn is_flagged(&self) -> bool {
-        self.display == DisplayState::Flagged
+        matches!(self.display, DisplayState::Flagged | DisplayState::Flags(_))
+    }
+
+    pub fn is_marked_unknown(&self) -> bool {
+        self.display == DisplayState::Unknown
+    }
+
+    pub fn flag_count(&self) -> usize {
+        match self.display {
+            DisplayState::Flagged => 1,
+            DisplayState::Flags(n) => n as usize,
+            _ => 0,
+        }
     }
 
     pub fn is_zero(&self) -> bool {
@@ -139,7 +259,24 @@ impl Cell {
         self.set_display(DisplayState::Flagged);
     }
 
-    pub fn get_value_display_string(&self) -> &str {
+    /// Puts `flags` flags on the cell, or clears it for 0.
+    pub fn set_display_to_flags(&mut self, flags: usize) {
+        match flags {
+            0 => { self.set_display(DisplayState::Default) },
+            1 => { self.set_display(DisplayState::Flagged) },
+            _ => { self.set_display(DisplayState::Flags(flags as u8)) },
+        }
+    }
+
+    pub fn set_display_to_unknown(&mut self) {
+        self.set_display(DisplayState::Unknown);
+    }
+
+    pub fn set_display_to_default(&mut self) {
+        self.set_display(DisplayState::Default);
+    }
+
+    pub fn get_value_display_string(&self) -> String {
         self.display.get_display_string()
     }
 
@@ -165,6 +302,7 @@ impl Cell {
             6 => { Value::Six },
             7 => { Value::Seven },
             8 => { Value::Eight },
+            9..=MAX_NUMBER => { Value::Many(neighboring_mines as u8) },
             _ => panic!("Unexpected number of neighbors: {}", neighboring_mines),
         }
     }
@@ -0,0 +1,1254 @@
+use crate::models::board_code::BoardCode;
+use crate::models::cell::Cell;
+use crate::models::neighborhood::Neighborhood;
+use crate::models::settings::{Difficulty, DimensionsError, Settings};
+use crate::models::solver::Solver;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// Checking a big board can take a few milliseconds a try, so keep to a budget the page can afford to freeze for
+const NO_GUESS_BUDGET_MILLISECONDS: f64 = 400.0;
+// Without a clock there's no budget to keep to, so a fixed number of tries keeps headless generation reproducible
+const MAX_NO_GUESS_ATTEMPTS: usize = 500;
+
+/// Reads a monotonic clock in milliseconds, so each frontend can time generation with its own timer.
+#[derive(Clone, Copy)]
+pub struct Clock(pub fn() -> f64);
+
+impl Clock {
+    pub fn now(&self) -> f64 {
+        (self.0)()
+    }
+}
+
+// Games are the same whatever they're timed with
+impl PartialEq for Clock {
+    fn eq(&self, _other: &Self) -> bool { true }
+}
+
+impl Eq for Clock {}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Clock")
+    }
+}
+
+/// Why a shared layout couldn't be played under the current rules.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LoadBoardError {
+    Dimensions(DimensionsError),
+    FirstClickIsMine,
+    FirstClickIsNotZero,
+}
+
+impl fmt::Display for LoadBoardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadBoardError::Dimensions(error)       => write!(f, "{}", error),
+            LoadBoardError::FirstClickIsMine        => write!(f, "The first click is on a mine, which the first click rule doesn't allow"),
+            LoadBoardError::FirstClickIsNotZero     => write!(f, "The first click isn't an opening, which the first click rule needs"),
+        }
+    }
+}
+
+impl std::error::Error for LoadBoardError {}
+
+impl From<DimensionsError> for LoadBoardError {
+    fn from(error: DimensionsError) -> Self { LoadBoardError::Dimensions(error) }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum GameState {
+    Ready,
+    Playing,
+    Won,
+    Lost,
+}
+
+/// The parts of a started game that change from move to move, for undo in practice mode.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Position {
+    state:                      GameState,
+    /// By index; narrowed down to the cells a move changed with [`Position::changed_in`].
+    cells:                      Vec<(usize, Cell)>,
+    shown_cells_count:          usize,
+    first_clicked_mine_index:   Option<usize>,
+}
+
+impl Position {
+    /// Keeps only the cells that differ in `game`, which are all a move from here changed.
+    pub fn changed_in(self, game: &Game) -> Self {
+        let cells = self.cells.into_iter().filter(|(index, cell)| game.cell(*index) != cell).collect();
+        Position { cells, ..self }
+    }
+}
+
+/// Serializes everything but the neighbors, which are worked out again from the dimensions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(try_from = "GameData")]
+pub struct Game {
+    settings:                   Settings,
+    seed:                       u64,
+    state:                      GameState,
+    cells:                      Vec<Cell>,
+    #[serde(skip)]
+    neighbors:                  Vec<HashSet<usize>>,
+    #[serde(skip)]
+    clock:                      Option<Clock>,
+    /// Sorted, with a cell holding several mines listed once for each of them.
+    mine_indices:               Vec<usize>,
+    shown_cells_count:          usize,
+    first_click_index:          Option<usize>,
+    first_clicked_mine_index:   Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct GameData {
+    settings:                   Settings,
+    seed:                       u64,
+    state:                      GameState,
+    cells:                      Vec<Cell>,
+    mine_indices:               Vec<usize>,
+    shown_cells_count:          usize,
+    first_click_index:          Option<usize>,
+    first_clicked_mine_index:   Option<usize>,
+}
+
+impl TryFrom<GameData> for Game {
+    type Error = &'static str;
+
+    fn try_from(data: GameData) -> Result<Self, Self::Error> {
+        let cell_count = data.settings.dimensions().cell_count();
+        if data.cells.len() != cell_count { return Err("the cells don't match the dimensions"); }
+        if data.shown_cells_count > cell_count { return Err("too many cells are shown"); }
+        let in_bounds = |index: &usize| *index < cell_count;
+        if !data.mine_indices.iter().all(in_bounds) ||
+           !data.first_click_index.iter().all(in_bounds) ||
+           !data.first_clicked_mine_index.iter().all(in_bounds) {
+            return Err("an index is off the board");
+        }
+
+        // Counting mined cells relies on repeats of an index sitting together
+        let mut mine_indices = data.mine_indices;
+        mine_indices.sort_unstable();
+        let mut mines_per_cell = vec![0; cell_count];
+        for index in &mine_indices {
+            mines_per_cell[*index] += 1;
+        }
+        if data.cells.iter().zip(&mines_per_cell).any(|(cell, mines)| cell.mine_count() != *mines) {
+            return Err("the mines don't match the cells");
+        }
+
+        let mut game = Game {
+            settings: data.settings,
+            seed: data.seed,
+            state: data.state,
+            cells: data.cells,
+            neighbors: Vec::new(),
+            clock: None,
+            mine_indices,
+            shown_cells_count: data.shown_cells_count,
+            first_click_index: data.first_click_index,
+            first_clicked_mine_index: data.first_clicked_mine_index,
+        };
+        game.neighbors = (0..cell_count).map(|index| game.calculate_neighbors(index)).collect();
+        Ok(game)
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self { Game::new(Settings::default()) }
+}
+
+impl Game {
+    pub fn new(settings: Settings) -> Self {
+        let mut game = Self {
+            settings,
+            seed: rand::random(),
+            state: GameState::Ready,
+            cells: Vec::new(),
+            neighbors: Vec::new(),
+            clock: None,
+            mine_indices: Vec::new(),
+            shown_cells_count: 0,
+            first_click_index: None,
+            first_clicked_mine_index: None,
+        };
+        game.resize();
+        game
+    }
+
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// The seed the mine layout is (or will be) generated from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn state(&self) -> GameState {
+        self.state
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn cell(&self, index: usize) -> &Cell {
+        &self.cells[index]
+    }
+
+    pub fn neighbors(&self, index: usize) -> &HashSet<usize> {
+        &self.neighbors[index]
+    }
+
+    pub fn neighbor_sets(&self) -> &[HashSet<usize>] {
+        &self.neighbors
+    }
+
+    pub fn mine_indices(&self) -> &[usize] {
+        &self.mine_indices
+    }
+
+    pub fn mines_count(&self) -> usize {
+        self.settings.dimensions().mines()
+    }
+
+    pub fn shown_cells_count(&self) -> usize {
+        self.shown_cells_count
+    }
+
+    pub fn first_click_index(&self) -> Option<usize> {
+        self.first_click_index
+    }
+
+    pub fn first_clicked_mine_index(&self) -> Option<usize> {
+        self.first_clicked_mine_index
+    }
+
+    /// Every flag on the board, counting each one on a cell with several.
+    pub fn count_flagged_mines(&self) -> usize {
+        self.cells.iter().map(|cell| cell.flag_count()).sum()
+    }
+
+    /// How many cells hold at least one mine.
+    pub fn mined_cells_count(&self) -> usize {
+        let repeats = self.mine_indices.windows(2).filter(|pair| pair[0] == pair[1]).count();
+        self.mine_indices.len() - repeats
+    }
+
+    pub fn has_started(&self) -> bool {
+        self.state != GameState::Ready
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == GameState::Ready || self.state == GameState::Playing
+    }
+
+    pub fn is_won(&self) -> bool {
+        self.state == GameState::Won
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.state == GameState::Lost
+    }
+
+    /// The position with every cell in it.
+    pub fn position(&self) -> Position {
+        self.position_of(0..self.cells.len())
+    }
+
+    /// The position with only the cells `other` covers, so restoring `other` can be taken back.
+    pub fn position_covering(&self, other: &Position) -> Position {
+        self.position_of(other.cells.iter().map(|(index, _)| *index))
+    }
+
+    /// Goes back (or forward) to `position`, which must have been taken since this board's first click.
+    pub fn restore_position(&mut self, position: Position) {
+        self.state = position.state;
+        for (index, cell) in position.cells {
+            self.cells[index] = cell;
+        }
+        self.shown_cells_count = position.shown_cells_count;
+        self.first_clicked_mine_index = position.first_clicked_mine_index;
+    }
+
+    /// Bounds no-guess generation by time rather than by a number of tries. Clocks aren't saved, so set it again after loading.
+    pub fn set_clock(&mut self, clock: Clock) {
+        self.clock = Some(clock);
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.settings.set_difficulty(difficulty);
+        self.resize();
+    }
+
+    /// Applies `settings`; anything that affects generation takes effect from the next board.
+    pub fn set_settings(&mut self, settings: Settings) {
+        let reshaped = !settings.has_same_layout(&self.settings);
+        self.settings = settings;
+        if reshaped { self.resize(); }
+    }
+
+    /// Starts a new game on a freshly drawn seed.
+    pub fn reset(&mut self) {
+        self.reset_with_seed(rand::random());
+    }
+
+    /// Starts a new game whose mines will be laid out from `seed` on the first click.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        for cell in self.cells.iter_mut() {
+            cell.reset();
+        }
+        self.mine_indices.clear();
+        self.shown_cells_count = 0;
+        self.first_click_index = None;
+        self.first_clicked_mine_index = None;
+        self.state = GameState::Ready;
+    }
+
+    /// The mine layout of the current game, or `None` before the first click has placed the mines.
+    pub fn board_code(&self) -> Option<BoardCode> {
+        if self.mine_indices.is_empty() && !self.has_started() { return None; }
+
+        let dimensions = self.settings.dimensions();
+        Some(BoardCode::new(dimensions, self.mine_indices.clone(), self.first_click_index).with_topology(self.settings.topology()).with_wrap_edges(self.settings.wrap_edges()).with_neighborhood(self.code_neighborhood()))
+    }
+
+    /// Starts a new game on exactly the layout in `board_code`, replaying its first click if it has one.
+    ///
+    /// The code's grid replaces the current one. Resetting lays out new mines on the same
+    /// dimensions, so a layout that couldn't have been generated under the current rules, or whose
+    /// first click breaks the first click rule, is rejected and the game is left as it was.
+    pub fn load_board(&mut self, board_code: &BoardCode) -> Result<(), LoadBoardError> {
+        let mut settings = self.settings;
+        board_code.apply_layout(&mut settings);
+        let dimensions = board_code.dimensions();
+        let dimensions = settings.validate_dimensions(dimensions.width(), dimensions.height(), dimensions.depth(), dimensions.mines())?;
+        settings.set_difficulty(Difficulty::from_dimensions(dimensions));
+
+        // Laid out on a fresh game first, since the first click can only be judged with the new neighbors
+        let mut loaded = Game { settings, seed: self.seed, clock: self.clock, ..Game::new(settings) };
+        loaded.cells = loaded.build_cells(board_code.mine_indices());
+        loaded.mine_indices = board_code.mine_indices().to_vec();
+        if let Some(index) = board_code.first_click_index() {
+            loaded.check_first_click(index)?;
+            loaded.reveal(index);
+        }
+
+        *self = loaded;
+        Ok(())
+    }
+
+    /// Reveals the cell at `index`, generating the board first if this is the opening click.
+    /// Returns whether anything on the board changed.
+    pub fn reveal(&mut self, index: usize) -> bool {
+        if !self.is_active() { return false; }
+
+        if self.state == GameState::Ready {
+            // A loaded board already has its mines in place
+            if self.mine_indices.is_empty() {
+                if let Err(error) = self.reassign_cells(index) {
+                    log::warn!("Couldn't lay out the mines: {}", error);
+                    return false;
+                }
+            }
+            self.first_click_index = Some(index);
+            self.state = GameState::Playing;
+        }
+
+        self.click_cell(index, true)
+    }
+
+    /// Cycles the cell at `index` through its flagged/unknown marks.
+    pub fn toggle_flag(&mut self, index: usize) -> bool {
+        if !self.is_active() || self.cells[index].is_shown() { return false; }
+
+        self.cells[index].cycle_display(self.settings.allow_mark_cell_as_unknown(), self.settings.max_mines_per_cell());
+        true
+    }
+
+    /// Adds one more flag to the cell at `index`, up to as many mines as a cell can hold.
+    pub fn add_flag(&mut self, index: usize) -> bool {
+        let flags = self.cells[index].flag_count();
+        if !self.is_active() || self.cells[index].is_shown() || flags >= self.settings.max_mines_per_cell() { return false; }
+
+        self.cells[index].set_display_to_flags(flags + 1);
+        true
+    }
+
+    /// Flags or clears the cell at `index` directly, regardless of the unknown-mark setting. Clearing takes off a `?` mark too.
+    pub fn set_flagged(&mut self, index: usize, flagged: bool) -> bool {
+        let cell = &self.cells[index];
+        let unchanged = if flagged { cell.is_flagged() } else { !cell.is_flagged() && !cell.is_marked_unknown() };
+        if !self.is_active() || cell.is_shown() || unchanged { return false; }
+
+        if flagged {
+            self.cells[index].set_display_to_flagged();
+        } else {
+            self.cells[index].set_display_to_default();
+        }
+        true
+    }
+
+    /// Puts the `?` mark on the cell at `index` in place of any flags, regardless of the unknown-mark setting.
+    pub fn mark_unknown(&mut self, index: usize) -> bool {
+        if !self.is_active() || self.cells[index].is_shown() || self.cells[index].is_marked_unknown() { return false; }
+
+        self.cells[index].set_display_to_unknown();
+        true
+    }
+
+    /// Reveals every neighbor of a shown cell whose flags add up to its number.
+    pub fn chord(&mut self, index: usize) -> bool {
+        if !self.is_active() || !self.cells[index].is_shown() { return false; }
+
+        let neighbors = self.neighbors[index].clone();
+        let neighboring_mines: usize = neighbors.iter().map(|index| self.cells[*index].mine_count()).sum();
+        let neighboring_flags: usize = neighbors.iter().map(|index| self.cells[*index].flag_count()).sum();
+        if neighboring_mines != neighboring_flags { return false; }
+
+        let mut changed = false;
+        for index in neighbors {
+            changed |= self.click_cell(index, true);
+        }
+        changed
+    }
+
+    /// The cell at `row` and `col` of the first layer.
+    pub fn get_index_from_row_col(&self, row: isize, col: isize) -> Option<usize> {
+        self.get_index_from_layer_row_col(0, row, col)
+    }
+
+    /// The cell at `row` and `col` of `layer`. Rows and columns wrap around to the other side if the edges wrap, but layers never do.
+    pub fn get_index_from_layer_row_col(&self, layer: isize, row: isize, col: isize) -> Option<usize> {
+        let dimensions = self.settings.dimensions();
+        let row = if self.settings.wraps_rows() { row.rem_euclid(dimensions.height() as isize) } else { row };
+        let col = if self.settings.wraps_columns() { col.rem_euclid(dimensions.width() as isize) } else { col };
+        if layer >= 0 && (layer as usize) < dimensions.depth() &&
+           row >= 0 && (row as usize) < dimensions.height() &&
+           col >= 0 && (col as usize) < dimensions.width() {
+            Some((layer as usize * dimensions.layer_cell_count()) + (row as usize * dimensions.width()) + col as usize)
+        } else {
+            None
+        }
+    }
+
+    /// The row and column of `index` within its layer.
+    pub fn get_row_col_from_index(&self, index: usize) -> (usize, usize) {
+        let index = index % self.settings.dimensions().layer_cell_count();
+        let row = index / self.settings.dimensions().width();
+        let col = index % self.settings.dimensions().width();
+
+        (row, col)
+    }
+
+    pub fn get_layer_from_index(&self, index: usize) -> usize {
+        index / self.settings.dimensions().layer_cell_count()
+    }
+
+    // Private methods
+    fn position_of(&self, indices: impl Iterator<Item = usize>) -> Position {
+        Position {
+            state: self.state,
+            cells: indices.map(|index| (index, self.cells[index].clone())).collect(),
+            shown_cells_count: self.shown_cells_count,
+            first_clicked_mine_index: self.first_clicked_mine_index,
+        }
+    }
+
+    fn check_first_click(&self, index: usize) -> Result<(), LoadBoardError> {
+        let cell = &self.cells[index];
+        if cell.is_mine() && !self.settings.first_click_setting_is_any() { return Err(LoadBoardError::FirstClickIsMine); }
+        if !cell.is_zero() && self.settings.first_click_setting_is_zero() { return Err(LoadBoardError::FirstClickIsNotZero); }
+        Ok(())
+    }
+
+    // Hexagons ignore the neighborhood setting, so their codes all keep the classic one
+    fn code_neighborhood(&self) -> Neighborhood {
+        if self.settings.neighborhood_is_disabled() { Neighborhood::default() } else { self.settings.neighborhood() }
+    }
+
+    fn resize(&mut self) {
+        let cell_count = self.settings.dimensions().cell_count();
+        self.cells = vec![Cell::new_empty(); cell_count];
+        self.neighbors = (0..cell_count).map(|index| self.calculate_neighbors(index)).collect();
+        self.reset_with_seed(self.seed);
+    }
+
+    fn reassign_cells(&mut self, index_clicked: usize) -> Result<(), DimensionsError> {
+        let (cells, mine_indices) = self.generate_cells(index_clicked, self.seed)?;
+        self.cells = cells;
+        self.mine_indices = mine_indices;
+        Ok(())
+    }
+
+    fn generate_cells(&self, index_clicked: usize, seed: u64) -> Result<(Vec<Cell>, Vec<usize>), DimensionsError> {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let (mut cells, mut mine_indices) = self.generate_layout(index_clicked, &mut rng)?;
+        if !self.settings.generation_setting_is_no_guess() { return Ok((cells, mine_indices)); }
+
+        let deadline = self.clock.map(|clock| (clock, clock.now() + NO_GUESS_BUDGET_MILLISECONDS));
+        let mut attempts = 1;
+        while !Solver::is_solvable(&self.neighbors, &cells, index_clicked) {
+            let out_of_budget = match deadline {
+                Some((clock, deadline)) => clock.now() >= deadline,
+                None => attempts == MAX_NO_GUESS_ATTEMPTS,
+            };
+            if out_of_budget {
+                log::warn!("No guess-free board found in {} attempts, the board may need a guess", attempts);
+                break;
+            }
+            (cells, mine_indices) = self.generate_layout(index_clicked, &mut rng)?;
+            attempts += 1;
+        }
+
+        Ok((cells, mine_indices))
+    }
+
+    fn generate_layout(&self, index_clicked: usize, rng: &mut ChaCha8Rng) -> Result<(Vec<Cell>, Vec<usize>), DimensionsError> {
+        let mut mine_counts: HashMap<usize, usize> = HashMap::new();
+        let index_neighbors = &self.neighbors[index_clicked];
+        // Drawing cells until one fits would never finish if there isn't room for every mine
+        let eligible_cells = (0..self.cells.len()).filter(|i| self.index_can_be_mine(index_clicked, *i, &mine_counts, index_neighbors)).count();
+        let room = eligible_cells * self.settings.max_mines_per_cell();
+        if self.mines_count() > room { return Err(DimensionsError::TooManyMines(room)); }
+
+        for _ in 0..self.mines_count() {
+            let mut i = self.get_random_cell_index(rng);
+            while !self.index_can_be_mine(index_clicked, i, &mine_counts, index_neighbors) {
+                i = self.get_random_cell_index(rng);
+            }
+            *mine_counts.entry(i).or_default() += 1;
+        }
+
+        let mut mine_indices: Vec<usize> = mine_counts
+            .iter()
+            .flat_map(|(index, count)| std::iter::repeat(*index).take(*count))
+            .collect();
+        mine_indices.sort_unstable();
+        Ok((self.build_cells(&mine_indices), mine_indices))
+    }
+
+    fn build_cells(&self, mine_indices: &[usize]) -> Vec<Cell> {
+        let mut mine_counts: HashMap<usize, usize> = HashMap::new();
+        for index in mine_indices {
+            *mine_counts.entry(*index).or_default() += 1;
+        }
+
+        self.neighbors
+            .iter()
+            .enumerate()
+            .map(|(cell_index, neighboring_cells)| match mine_counts.get(&cell_index) {
+                Some(mines) => Cell::new_mines(*mines),
+                None => Cell::new(Some(neighboring_cells.iter().filter_map(|index| mine_counts.get(index)).sum())),
+            })
+            .collect()
+    }
+
+    fn index_can_be_mine(&self, index_clicked: usize, mine_index: usize, mine_counts: &HashMap<usize, usize>, neighbors: &HashSet<usize>) -> bool {
+        if mine_counts.get(&mine_index).copied().unwrap_or(0) >= self.settings.max_mines_per_cell() { return false; }
+        if index_clicked == mine_index { return self.settings.first_click_setting_is_any(); }
+        if neighbors.contains(&mine_index) && self.settings.first_click_setting_is_zero() { return false; }
+        true
+    }
+
+    // On layered boards the cells directly above and below count too, along with their neighbors
+    fn calculate_neighbors(&self, index: usize) -> HashSet<usize> {
+        let layer = self.get_layer_from_index(index) as isize;
+        let (row, col) = self.get_row_col_from_index(index);
+        let offsets = self.settings.neighbor_offsets(row);
+        let layer_offsets = if self.settings.dimensions().is_layered() { -1..=1 } else { 0..=0 };
+
+        layer_offsets
+            .flat_map(|layer_offset| {
+                let straight_across = (layer_offset != 0).then_some(&(0, 0));
+                straight_across.into_iter().chain(&offsets).map(move |offset| (layer_offset, *offset))
+            })
+            .filter_map(|(layer_offset, (row_offset, col_offset))| {
+                self.get_index_from_layer_row_col(layer + layer_offset, row as isize + row_offset, col as isize + col_offset)
+            })
+            // Wrapping round a narrow board can lead back to the cell itself
+            .filter(|neighbor| *neighbor != index)
+            .collect()
+    }
+
+    fn get_random_cell_index(&self, rng: &mut ChaCha8Rng) -> usize {
+        rng.gen_range(0..self.cells.len())
+    }
+
+    // `by_player` is false when a mine is uncovered as part of the end-of-game reveal
+    fn click_cell(&mut self, index: usize, by_player: bool) -> bool {
+        if !self.is_active() && by_player { return false; }
+        if self.cells[index].is_shown() || self.cells[index].is_flagged() { return false; }
+
+        self.cells[index].handle_click();
+
+        if self.cells[index].is_mine() {
+            if by_player { self.handle_loss(index); }
+            return true;
+        }
+
+        self.shown_cells_count += 1;
+
+        // Recursively click all neighboring cells if we clicked a 0
+        if self.cells[index].is_zero() { self.click_neighboring_empty_cells(index); }
+        self.check_for_win();
+        true
+    }
+
+    fn click_neighboring_empty_cells(&mut self, index: usize) {
+        let neighbors = self.neighbors[index].clone();
+        for index in neighbors {
+            self.click_cell(index, true);
+        }
+    }
+
+    fn click_all_mines(&mut self) {
+        for i in 0..self.mine_indices.len() {
+            let index = self.mine_indices[i];
+            self.click_cell(index, false);
+        }
+    }
+
+    fn check_for_win(&mut self) {
+        if self.state == GameState::Playing && self.shown_cells_count + self.mined_cells_count() == self.cells.len() {
+            self.handle_win();
+        }
+    }
+
+    fn handle_loss(&mut self, index: usize) {
+        self.first_clicked_mine_index = Some(index);
+        self.state = GameState::Lost;
+        self.click_all_mines();
+    }
+
+    fn handle_win(&mut self) {
+        self.state = GameState::Won;
+        self.flag_all_mines();
+    }
+
+    fn flag_all_mines(&mut self) {
+        for index in &self.mine_indices {
+            let mines = self.cells[*index].mine_count();
+            self.cells[*index].set_display_to_flags(mines);
+        }
+    }
+}
+
+/// A game on exactly `mine_indices` with each of `shown` clicked in turn, for tests. Any first click
+/// goes, so the layout isn't bound by a safe zone. A one-row board is easy to picture: `0 1 * 1 0`
+/// for a mine in the middle of five cells.
+#[cfg(test)]
+pub(crate) fn test_board(width: usize, height: usize, mine_indices: &[usize], shown: &[usize]) -> Game {
+    use crate::models::settings::{Dimensions, FirstClickSetting};
+
+    let mut settings = Settings::default();
+    settings.set_first_click_setting(FirstClickSetting::Any);
+    let mut game = Game::new(settings);
+    game.load_board(&BoardCode::new(Dimensions::new(width, height, mine_indices.len()), mine_indices.to_vec(), None)).expect("the layout fits");
+    for index in shown {
+        game.reveal(*index);
+    }
+    game
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::cell::Value;
+    use crate::models::neighborhood::NeighborMask;
+    use crate::models::settings::{Dimensions, FirstClickSetting, GenerationSetting};
+    use crate::models::topology::Topology;
+    use serde_json::json;
+
+    fn game_with_first_click(first_click_setting: FirstClickSetting, dimensions: Dimensions, seed: u64) -> Game {
+        let mut settings = Settings::default();
+        settings.set_first_click_setting(first_click_setting);
+        settings.set_difficulty(Difficulty::Custom(dimensions));
+        let mut game = Game::new(settings);
+        game.reset_with_seed(seed);
+        game
+    }
+
+    // Like `test_board`, but on the grid and neighborhood `board_code` describes
+    fn loaded(board_code: BoardCode, shown: &[usize]) -> Game {
+        let mut settings = Settings::default();
+        settings.set_first_click_setting(FirstClickSetting::Any);
+        let mut game = Game::new(settings);
+        game.load_board(&board_code).expect("the layout fits");
+        for index in shown {
+            game.reveal(*index);
+        }
+        game
+    }
+
+    fn sorted_neighbors(game: &Game, index: usize) -> Vec<usize> {
+        let mut neighbors: Vec<usize> = game.neighbors(index).iter().copied().collect();
+        neighbors.sort_unstable();
+        neighbors
+    }
+
+    #[test]
+    fn revealing_a_number_shows_only_that_cell() {
+        let mut game = test_board(5, 1, &[2], &[]);
+        assert!(game.reveal(1));
+
+        assert_eq!(game.state(), GameState::Playing);
+        assert_eq!(game.cell(1).shown_number(), Some(1));
+        assert_eq!(game.shown_cells_count(), 1);
+        assert!(!game.cell(0).is_shown());
+    }
+
+    #[test]
+    fn revealing_a_zero_floods_up_to_the_numbers() {
+        let mut game = test_board(5, 1, &[2], &[]);
+        game.reveal(0);
+
+        assert!(game.cell(0).is_shown() && game.cell(1).is_shown());
+        assert!(!game.cell(2).is_shown() && !game.cell(3).is_shown());
+        assert_eq!(game.shown_cells_count(), 2);
+    }
+
+    #[test]
+    fn flood_fill_crosses_connected_zeros() {
+        // Mines in the top left and bottom right corners leave one connected opening
+        let mut game = test_board(5, 5, &[0, 24], &[]);
+        game.reveal(4);
+
+        assert!(game.is_won());
+        assert_eq!(game.shown_cells_count(), 23);
+    }
+
+    #[test]
+    fn revealing_every_safe_cell_wins_and_flags_the_mines() {
+        let mut game = test_board(5, 1, &[2], &[]);
+        game.reveal(0);
+        assert!(game.is_active());
+        game.reveal(4);
+
+        assert!(game.is_won());
+        assert!(game.cell(2).is_flagged());
+        assert!(!game.reveal(2));
+    }
+
+    #[test]
+    fn revealing_a_mine_loses_and_shows_every_mine() {
+        let mut game = test_board(5, 1, &[0, 3], &[]);
+        game.reveal(1);
+        game.reveal(0);
+
+        assert!(game.is_lost());
+        assert_eq!(game.first_clicked_mine_index(), Some(0));
+        assert!(game.cell(3).is_shown());
+        assert!(!game.toggle_flag(4));
+    }
+
+    #[test]
+    fn restoring_a_position_takes_back_a_loss() {
+        let mut game = test_board(5, 1, &[0, 3], &[]);
+        game.reveal(1);
+        let before = game.position();
+        game.reveal(0);
+        game.restore_position(before.clone());
+
+        assert!(game.is_active());
+        assert_eq!(game.position(), before);
+        assert_eq!(game.first_clicked_mine_index(), None);
+        assert!(!game.cell(0).is_shown());
+    }
+
+    #[test]
+    fn a_position_changed_in_a_move_keeps_only_the_changed_cells() {
+        let mut game = test_board(11, 1, &[0, 7, 10], &[1]);
+        let before = game.position();
+        game.reveal(4);
+
+        let changes = before.clone().changed_in(&game);
+        assert_eq!(changes.cells.iter().map(|(index, _)| *index).collect::<Vec<_>>(), vec![2, 3, 4, 5, 6]);
+        assert_eq!(game.position_covering(&changes).cells.len(), 5);
+
+        game.restore_position(changes);
+        assert_eq!(game.position(), before);
+    }
+
+    #[test]
+    fn chord_reveals_neighbors_once_the_flags_match() {
+        let mut game = test_board(5, 1, &[2], &[]);
+        game.reveal(1);
+        assert!(!game.chord(1));
+
+        game.toggle_flag(2);
+        assert!(game.chord(1));
+        assert!(game.cell(0).is_shown());
+        assert!(!game.cell(2).is_shown());
+    }
+
+    #[test]
+    fn chord_with_a_wrong_flag_loses() {
+        let mut game = test_board(5, 1, &[2], &[]);
+        game.reveal(1);
+        game.toggle_flag(0);
+        game.chord(1);
+
+        assert!(game.is_lost());
+        assert_eq!(game.first_clicked_mine_index(), Some(2));
+    }
+
+    #[test]
+    fn chord_ignores_hidden_cells() {
+        let mut game = test_board(5, 1, &[2], &[]);
+        game.reveal(0);
+        assert!(!game.chord(3));
+    }
+
+    #[test]
+    fn zero_first_click_always_opens() {
+        for seed in 0..100 {
+            let mut game = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(9, 9, 10), seed);
+            game.reveal(40);
+
+            assert!(game.cell(40).is_zero(), "seed {}", seed);
+            assert!(game.neighbors(40).iter().all(|index| !game.cell(*index).is_mine()), "seed {}", seed);
+            assert_eq!(game.mine_indices().len(), 10);
+        }
+    }
+
+    #[test]
+    fn safe_first_click_is_never_a_mine() {
+        // Every other cell is a mine, so only the rule keeps the click safe
+        for seed in 0..20 {
+            let mut game = game_with_first_click(FirstClickSetting::Safe, Dimensions::new(3, 3, 8), seed);
+            game.reveal(4);
+
+            assert!(!game.cell(4).is_mine(), "seed {}", seed);
+            assert_eq!(game.cell(4).shown_number(), Some(8));
+            assert!(game.is_won());
+        }
+    }
+
+    #[test]
+    fn any_first_click_can_be_a_mine() {
+        let mut game = game_with_first_click(FirstClickSetting::Any, Dimensions::new(3, 3, 9), 1);
+        game.reveal(4);
+
+        assert!(game.is_lost());
+    }
+
+    #[test]
+    fn loading_a_board_too_dense_to_lay_out_again_is_rejected() {
+        // Five mines leave no room for a zero first click's safe zone on a 3x3 board
+        let board_code = BoardCode::new(Dimensions::new(3, 3, 0), vec![0, 1, 2, 3, 5], None);
+        let mut game = Game::new(Settings::default());
+
+        assert_eq!(game.load_board(&board_code), Err(LoadBoardError::Dimensions(DimensionsError::TooManyMines(0))));
+        assert_eq!(game.settings(), Settings::default());
+    }
+
+    fn load_under(first_click_setting: FirstClickSetting, first_click_index: usize) -> (Game, Result<(), LoadBoardError>) {
+        let mut settings = Settings::default();
+        settings.set_first_click_setting(first_click_setting);
+        let mut game = Game::new(settings);
+        let result = game.load_board(&BoardCode::new(Dimensions::new(5, 1, 0), vec![0], Some(first_click_index)));
+        (game, result)
+    }
+
+    #[test]
+    fn loading_a_board_that_opens_on_a_mine_is_rejected_unless_any_click_goes() {
+        for first_click_setting in [FirstClickSetting::Safe, FirstClickSetting::Zero] {
+            let (game, result) = load_under(first_click_setting, 0);
+            assert_eq!(result, Err(LoadBoardError::FirstClickIsMine));
+            assert_eq!(game.state(), GameState::Ready);
+            assert_eq!(game.settings().difficulty(), Settings::default().difficulty());
+        }
+
+        let (game, result) = load_under(FirstClickSetting::Any, 0);
+        assert_eq!(result, Ok(()));
+        assert!(game.is_lost());
+    }
+
+    #[test]
+    fn loading_a_board_that_opens_on_a_number_is_rejected_under_zero() {
+        let (_, result) = load_under(FirstClickSetting::Zero, 1);
+        assert_eq!(result, Err(LoadBoardError::FirstClickIsNotZero));
+
+        let (game, result) = load_under(FirstClickSetting::Safe, 1);
+        assert_eq!(result, Ok(()));
+        assert!(game.cell(1).is_shown());
+
+        let (game, result) = load_under(FirstClickSetting::Zero, 4);
+        assert_eq!(result, Ok(()));
+        assert!(game.cell(2).is_shown());
+    }
+
+    #[test]
+    fn loading_a_board_switches_to_its_grid() {
+        let board_code = BoardCode::new(Dimensions::new(4, 4, 0), vec![5], None).with_topology(Topology::Hex);
+        let mut game = Game::default();
+        game.load_board(&board_code).expect("the layout fits");
+
+        assert_eq!(game.settings().topology(), Topology::Hex);
+        assert_eq!(game.neighbors(5).len(), 6);
+    }
+
+    #[test]
+    fn loading_a_board_wraps_its_edges_if_the_code_does() {
+        let board_code = BoardCode::new(Dimensions::new(5, 5, 0), vec![12], None).with_wrap_edges(true);
+        let mut game = Game::default();
+        game.load_board(&board_code).expect("the layout fits");
+
+        assert!(game.settings().wrap_edges());
+        assert_eq!(game.neighbors(0).len(), 8);
+        assert_eq!(game.board_code(), Some(board_code));
+    }
+
+    #[test]
+    fn loading_a_board_takes_its_neighborhood() {
+        let board_code = BoardCode::new(Dimensions::new(5, 5, 0), vec![12], None).with_neighborhood(Neighborhood::VonNeumann);
+        let mut game = Game::default();
+        game.load_board(&board_code).expect("the layout fits");
+
+        assert_eq!(game.settings().neighborhood(), Neighborhood::VonNeumann);
+        assert_eq!(game.neighbors(12).len(), 4);
+        assert_eq!(game.board_code(), Some(board_code));
+    }
+
+    // The game as JSON, for making saves that were never written by a game
+    fn saved(game: &Game) -> serde_json::Value {
+        serde_json::to_value(game).expect("a game serializes")
+    }
+
+    fn read(save: serde_json::Value) -> Result<Game, serde_json::Error> {
+        serde_json::from_value(save)
+    }
+
+    #[test]
+    fn a_saved_game_reads_back_the_same() {
+        let mut game = test_board(5, 1, &[0, 3], &[]);
+        game.reveal(1);
+        game.toggle_flag(0);
+
+        assert_eq!(read(saved(&game)).expect("the save reads"), game);
+    }
+
+    #[test]
+    fn saves_with_counts_another_variant_stands_for_are_rejected() {
+        let game = test_board(5, 1, &[0, 3], &[]);
+        for (field, value) in [("value", json!({ "Mines": 1 })), ("value", json!({ "Many": 3 })), ("display", json!({ "Flags": 1 })), ("display", json!({ "Shown": "Two" }))] {
+            let mut save = saved(&game);
+            save["cells"][0][field] = value;
+            assert!(read(save).is_err(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn saves_with_unsorted_mines_are_sorted() {
+        let mut game = test_board(4, 4, &[0, 15], &[]);
+        game.reveal(5);
+        let mut save = saved(&game);
+        save["mine_indices"] = json!([15, 0]);
+
+        let read_back = read(save).expect("the save reads");
+        assert_eq!(read_back.mine_indices(), &[0, 15]);
+        assert_eq!(read_back, game);
+    }
+
+    #[test]
+    fn saves_whose_mines_dont_match_the_cells_are_rejected() {
+        let game = test_board(5, 1, &[0, 3], &[]);
+        let mut save = saved(&game);
+        save["mine_indices"] = json!([0, 4]);
+
+        assert!(read(save).is_err());
+    }
+
+    #[test]
+    fn hexagons_lean_the_other_way_on_odd_rows() {
+        let game = loaded(BoardCode::new(Dimensions::new(4, 4, 0), vec![], None).with_topology(Topology::Hex), &[]);
+
+        assert_eq!(sorted_neighbors(&game, 9), vec![4, 5, 8, 10, 12, 13]);
+        assert_eq!(sorted_neighbors(&game, 5), vec![1, 2, 4, 6, 9, 10]);
+        assert_eq!(sorted_neighbors(&game, 0), vec![1, 4]);
+    }
+
+    #[test]
+    fn hexagons_count_up_to_six_mines() {
+        let game = loaded(BoardCode::new(Dimensions::new(4, 4, 0), vec![4, 5, 8, 10, 12, 13], None).with_topology(Topology::Hex), &[]);
+
+        assert_eq!(game.cell(9).value().number(), Some(6));
+        assert_eq!(game.cell(1).value().number(), Some(2));
+    }
+
+    #[test]
+    fn hexagons_flood_and_chord_along_their_own_neighbors() {
+        // Only cell 3 is a mine, so revealing the far corner opens the whole board
+        let board_code = BoardCode::new(Dimensions::new(4, 4, 0), vec![3], None).with_topology(Topology::Hex);
+        let game = loaded(board_code.clone(), &[12]);
+        assert!(game.is_won());
+
+        let mut game = loaded(board_code, &[2]);
+        game.toggle_flag(3);
+        assert!(game.chord(2));
+        assert!(game.cell(6).is_shown());
+        assert!(game.cell(7).is_shown());
+    }
+
+    #[test]
+    fn wrapped_edges_give_every_cell_eight_neighbors() {
+        let game = loaded(BoardCode::new(Dimensions::new(5, 5, 0), vec![], None).with_wrap_edges(true), &[]);
+
+        assert!((0..25).all(|index| game.neighbors(index).len() == 8));
+        assert_eq!(sorted_neighbors(&game, 0), vec![1, 4, 5, 6, 9, 20, 21, 24]);
+    }
+
+    #[test]
+    fn flood_fill_wraps_round_the_edges() {
+        // . 1 * 1 . .  Once the edges wrap, the left edge opens round to the right one and on to the other side of the mine
+        let board_code = BoardCode::new(Dimensions::new(6, 1, 0), vec![2], None);
+        assert!(!loaded(board_code.clone(), &[0]).cell(3).is_shown());
+        assert!(loaded(board_code.with_wrap_edges(true), &[0]).is_won());
+    }
+
+    #[test]
+    fn the_first_click_safe_zone_wraps_round_the_edges() {
+        let mut game = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(5, 5, 16), 0);
+        let mut settings = game.settings();
+        settings.set_wrap_edges(true);
+        game.set_settings(settings);
+        game.reset_with_seed(0);
+        game.reveal(0);
+
+        let safe_zone = [0, 1, 4, 5, 6, 9, 20, 21, 24];
+        assert!(safe_zone.iter().all(|index| !game.cell(*index).is_mine()));
+        assert_eq!(game.mine_indices().len(), 16);
+    }
+
+    #[test]
+    fn hexagons_only_wrap_sideways_with_an_odd_number_of_rows() {
+        let odd = loaded(BoardCode::new(Dimensions::new(4, 3, 0), vec![], None).with_topology(Topology::Hex).with_wrap_edges(true), &[]);
+        assert_eq!(sorted_neighbors(&odd, 0), vec![1, 3, 4, 7]);
+
+        let even = loaded(BoardCode::new(Dimensions::new(4, 4, 0), vec![], None).with_topology(Topology::Hex).with_wrap_edges(true), &[]);
+        assert!((0..16).all(|index| even.neighbors(index).len() == 6));
+    }
+
+    #[test]
+    fn the_middle_of_a_cube_has_twenty_six_neighbors() {
+        let game = loaded(BoardCode::new(Dimensions::new(3, 3, 0).with_depth(3), vec![], None), &[]);
+
+        assert_eq!(game.neighbors(13).len(), 26);
+        assert_eq!(game.neighbors(0).len(), 7);
+        assert_eq!(game.neighbors(4).len(), 17);
+    }
+
+    #[test]
+    fn layers_never_wrap() {
+        let game = loaded(BoardCode::new(Dimensions::new(3, 3, 0).with_depth(3), vec![], None).with_wrap_edges(true), &[]);
+
+        assert_eq!(game.neighbors(13).len(), 26);
+        assert_eq!(game.neighbors(4).len(), 17);
+        assert_eq!(game.get_index_from_layer_row_col(3, 0, 0), None);
+        assert_eq!(game.get_index_from_layer_row_col(-1, 0, 0), None);
+    }
+
+    #[test]
+    fn flood_fill_crosses_layers() {
+        // Three layers of three cells with a mine in the last one; the bottom layer is only reached through the middle one
+        let game = loaded(BoardCode::new(Dimensions::new(3, 1, 0).with_depth(3), vec![8], None), &[0]);
+
+        assert!(game.cell(6).is_shown());
+        assert_eq!(game.cell(7).value().number(), Some(1));
+        assert!(game.is_won());
+    }
+
+    #[test]
+    fn cells_count_mines_on_the_layers_above_and_below() {
+        let game = loaded(BoardCode::new(Dimensions::new(3, 3, 0).with_depth(3), vec![4, 22], None), &[]);
+
+        assert_eq!(game.cell(13).value().number(), Some(2));
+        assert_eq!(game.cell(0).value().number(), Some(1));
+        assert_eq!(game.get_layer_from_index(22), 2);
+        assert_eq!(game.get_row_col_from_index(22), (1, 1));
+    }
+
+    #[test]
+    fn knights_count_the_mines_a_knight_could_jump_to() {
+        let knight_moves = vec![1, 3, 5, 9, 15, 19, 21, 23];
+        let game = loaded(BoardCode::new(Dimensions::new(5, 5, 0), knight_moves.clone(), None).with_neighborhood(Neighborhood::Knight), &[]);
+
+        assert_eq!(sorted_neighbors(&game, 12), knight_moves);
+        assert_eq!(game.cell(12).value().number(), Some(8));
+        assert_eq!(game.cell(0).value().number(), Some(0));
+    }
+
+    #[test]
+    fn wide_neighborhoods_count_past_eight() {
+        let mines: Vec<usize> = (0..25).filter(|index| *index != 12).collect();
+        let game = loaded(BoardCode::new(Dimensions::new(5, 5, 0), mines, None).with_neighborhood(Neighborhood::Radius2), &[12]);
+
+        assert_eq!(game.cell(12).value().number(), Some(24));
+        assert_eq!(game.cell(12).value(), Value::Many(24));
+        assert!(game.is_won());
+    }
+
+    #[test]
+    fn custom_neighborhoods_flood_along_their_offsets() {
+        // Each cell only sees the cells two to either side, so the odd cells are cut off from the even ones
+        let skip_one = Neighborhood::Custom(NeighborMask::from_offsets(&[(0, -2), (0, 2)]));
+        let game = loaded(BoardCode::new(Dimensions::new(7, 1, 0), vec![5], None).with_neighborhood(skip_one), &[0]);
+
+        assert!([0, 2, 4, 6].iter().all(|index| game.cell(*index).is_shown()));
+        assert!(!game.cell(1).is_shown());
+        assert_eq!(game.cell(3).value().number(), Some(1));
+    }
+
+    // Three mines in the first cell and one in the fourth, so the safe cells read 3, 1 and 1
+    fn multi_mine_board(shown: &[usize]) -> Game {
+        loaded(BoardCode::new(Dimensions::new(5, 1, 0), vec![0, 0, 0, 3], None), shown)
+    }
+
+    #[test]
+    fn flags_cycle_up_to_the_most_mines_a_cell_can_hold() {
+        let mut game = multi_mine_board(&[]);
+        assert_eq!(game.settings().max_mines_per_cell(), 3);
+
+        let flag_counts: Vec<usize> = (0..4).map(|_| { game.toggle_flag(0); game.cell(0).flag_count() }).collect();
+        assert_eq!(flag_counts, vec![1, 2, 3, 0]);
+    }
+
+    #[test]
+    fn chording_needs_the_flags_to_add_up_to_the_mines() {
+        let mut game = multi_mine_board(&[1]);
+        assert_eq!(game.cell(1).value().number(), Some(3));
+
+        game.toggle_flag(0);
+        game.toggle_flag(0);
+        assert!(!game.chord(1));
+        game.toggle_flag(0);
+        assert!(game.chord(1));
+        assert!(game.cell(2).is_shown());
+        assert!(game.is_active());
+    }
+
+    #[test]
+    fn cells_with_several_mines_count_each_of_them() {
+        let mut game = multi_mine_board(&[]);
+        game.add_flag(0);
+        game.add_flag(0);
+        game.toggle_flag(3);
+
+        assert_eq!(game.mine_indices().len(), 4);
+        assert_eq!(game.mined_cells_count(), 2);
+        assert_eq!(game.count_flagged_mines(), 3);
+        assert_eq!(game.cell(0).mine_count(), 3);
+    }
+
+    #[test]
+    fn generation_can_stack_mines_in_a_cell() {
+        let mut game = game_with_first_click(FirstClickSetting::Safe, Dimensions::new(3, 3, 20), 3);
+        let mut settings = game.settings();
+        settings.set_max_mines_per_cell(3);
+        settings.set_generation_setting(GenerationSetting::NoGuess);
+        game.set_settings(settings);
+        game.reset_with_seed(3);
+        game.reveal(4);
+
+        assert_eq!(game.mine_indices().len(), 20);
+        assert!(game.mined_cells_count() >= 7);
+        assert!(game.cells().iter().all(|cell| cell.mine_count() <= 3));
+        assert_eq!(game.cell(4).value().number(), Some(20));
+    }
+
+    #[test]
+    fn generation_gives_up_when_the_mines_dont_fit() {
+        let mut game = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(3, 3, 5), 1);
+
+        assert!(!game.reveal(4));
+        assert_eq!(game.state(), GameState::Ready);
+        assert!(game.mine_indices().is_empty());
+    }
+
+    #[test]
+    fn no_guess_boards_can_be_solved_from_the_first_click() {
+        let mut settings = Settings::default();
+        settings.set_difficulty(Difficulty::Custom(Dimensions::new(9, 9, 15)));
+        let solvable = |settings: Settings, seed: u64| {
+            let mut game = Game::new(settings);
+            game.reset_with_seed(seed);
+            game.reveal(40);
+            Solver::is_solvable(game.neighbor_sets(), game.cells(), 40)
+        };
+
+        // Dense enough that plenty of ordinary boards need a guess
+        assert!((0..10).any(|seed| !solvable(settings, seed)));
+        settings.set_generation_setting(GenerationSetting::NoGuess);
+        assert!((0..10).all(|seed| solvable(settings, seed)));
+    }
+
+    #[test]
+    fn no_guess_generation_keeps_to_its_time_budget() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Every reading is a second later than the last, so the budget runs out at the first check
+        static READINGS: AtomicUsize = AtomicUsize::new(0);
+        fn slow_clock() -> f64 { READINGS.fetch_add(1, Ordering::SeqCst) as f64 * 1000.0 }
+
+        let mut settings = Settings::default();
+        settings.set_generation_setting(GenerationSetting::NoGuess);
+        settings.set_difficulty(Difficulty::Custom(Dimensions::new(16, 16, 80)));
+        let mut game = Game::new(settings);
+        game.set_clock(Clock(slow_clock));
+        game.reset_with_seed(3);
+
+        assert!(game.reveal(0));
+        assert!(READINGS.load(Ordering::SeqCst) <= 2);
+        assert_eq!(game.mine_indices().len(), 80);
+    }
+
+    #[test]
+    fn the_same_seed_lays_out_the_same_board() {
+        let mut first = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(16, 16, 40), 42);
+        let mut second = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(16, 16, 40), 42);
+        first.reveal(100);
+        second.reveal(100);
+
+        assert_eq!(first.mine_indices(), second.mine_indices());
+    }
+
+    #[test]
+    fn a_seed_keeps_laying_out_the_same_board_across_versions() {
+        // Shared seeds should open the same board for everyone, so generation mustn't drift
+        let mut game = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(9, 9, 10), 1);
+        game.reveal(40);
+
+        assert_eq!(game.seed(), 1);
+        assert_eq!(game.mine_indices(), &[1, 6, 7, 8, 12, 21, 37, 42, 57, 80]);
+    }
+
+    #[test]
+    fn restarting_on_a_seed_lays_out_its_board_again() {
+        let mut game = game_with_first_click(FirstClickSetting::Safe, Dimensions::new(16, 16, 40), 5);
+        game.reveal(0);
+        let first_layout = game.mine_indices().to_vec();
+        game.reset_with_seed(5);
+        game.reveal(0);
+
+        assert_eq!(game.mine_indices(), &first_layout[..]);
+    }
+
+    #[test]
+    fn different_seeds_lay_out_different_boards() {
+        let mut first = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(16, 16, 40), 1);
+        let mut second = game_with_first_click(FirstClickSetting::Zero, Dimensions::new(16, 16, 40), 2);
+        first.reveal(100);
+        second.reveal(100);
+
+        assert_ne!(first.mine_indices(), second.mine_indices());
+    }
+}
@@ -0,0 +1,148 @@
+use crate::models::game::{Game, Position};
+use crate::models::metrics::ClickCounts;
+use std::collections::VecDeque;
+
+// Snapshots only hold the cells a move changed, but keep a limit so a long session can't grow forever
+const MAX_SNAPSHOTS: usize = 1000;
+
+/// A position together with the clicks it took to reach it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snapshot {
+    position: Position,
+    clicks: ClickCounts,
+}
+
+impl Snapshot {
+    pub fn new(position: Position, clicks: ClickCounts) -> Self {
+        Snapshot { position, clicks }
+    }
+
+    // Puts the game back to this snapshot, returning what it replaced so that can be restored in turn
+    fn restore(self, game: &mut Game, clicks: &mut ClickCounts) -> Snapshot {
+        let replaced = Snapshot::new(game.position_covering(&self.position), *clicks);
+        game.restore_position(self.position);
+        *clicks = self.clicks;
+        replaced
+    }
+}
+
+/// The cells each move changed, for undo and redo in practice mode.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct History {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Remembers `before`, the game as it was before the move that led to `game`. Anything that could be redone is forgotten.
+    pub fn record(&mut self, before: Snapshot, game: &Game) {
+        if self.undo.len() == MAX_SNAPSHOTS { self.undo.pop_front(); }
+        let position = before.position.changed_in(game);
+        self.undo.push_back(Snapshot { position, ..before });
+        self.redo.clear();
+    }
+
+    /// Takes back the last move, returning whether there was one.
+    pub fn undo(&mut self, game: &mut Game, clicks: &mut ClickCounts) -> bool {
+        let Some(previous) = self.undo.pop_back() else { return false; };
+        self.redo.push(previous.restore(game, clicks));
+        true
+    }
+
+    /// Makes the last undone move again, returning whether there was one.
+    pub fn redo(&mut self, game: &mut Game, clicks: &mut ClickCounts) -> bool {
+        let Some(next) = self.redo.pop() else { return false; };
+        self.undo.push_back(next.restore(game, clicks));
+        true
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::test_board;
+
+    // Makes a move the way the app does, remembering the game from before it
+    fn play(history: &mut History, game: &mut Game, clicks: &mut ClickCounts, make_move: impl FnOnce(&mut Game)) {
+        let before = Snapshot::new(game.position(), *clicks);
+        clicks.add_left();
+        make_move(game);
+        history.record(before, game);
+    }
+
+    #[test]
+    fn undo_and_redo_move_the_board_and_clicks_back_and_forth() {
+        let mut history = History::default();
+        let mut game = test_board(5, 1, &[0, 3], &[1]);
+        let mut clicks = ClickCounts::default();
+        let start = game.clone();
+        play(&mut history, &mut game, &mut clicks, |game| { game.reveal(2); });
+        let after = game.clone();
+
+        assert!(history.undo(&mut game, &mut clicks));
+        assert_eq!(game, start);
+        assert_eq!(clicks.total(), 0);
+        assert!(!history.can_undo());
+
+        assert!(history.redo(&mut game, &mut clicks));
+        assert_eq!(game, after);
+        assert_eq!(clicks.total(), 1);
+        assert!(!history.redo(&mut game, &mut clicks));
+    }
+
+    #[test]
+    fn undoing_a_loss_carries_on_the_game() {
+        let mut history = History::default();
+        let mut game = test_board(5, 1, &[0, 3], &[1]);
+        let mut clicks = ClickCounts::default();
+        play(&mut history, &mut game, &mut clicks, |game| { game.reveal(3); });
+        assert!(game.is_lost());
+
+        history.undo(&mut game, &mut clicks);
+        assert!(game.is_active());
+        assert!(!game.cell(3).is_shown());
+        assert_eq!(game.first_clicked_mine_index(), None);
+    }
+
+    #[test]
+    fn a_new_move_forgets_what_could_be_redone() {
+        let mut history = History::default();
+        let mut game = test_board(5, 1, &[0, 3], &[1]);
+        let mut clicks = ClickCounts::default();
+        play(&mut history, &mut game, &mut clicks, |game| { game.reveal(2); });
+        history.undo(&mut game, &mut clicks);
+        assert!(history.can_redo());
+
+        play(&mut history, &mut game, &mut clicks, |game| { game.toggle_flag(0); });
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn only_the_latest_snapshots_are_kept() {
+        let mut history = History::default();
+        let mut game = test_board(5, 1, &[0, 3], &[1]);
+        let mut clicks = ClickCounts::default();
+        for _ in 0..MAX_SNAPSHOTS + 5 {
+            play(&mut history, &mut game, &mut clicks, |game| { game.toggle_flag(0); });
+        }
+
+        let mut undone = 0;
+        while history.undo(&mut game, &mut clicks) { undone += 1; }
+        assert_eq!(undone, MAX_SNAPSHOTS);
+        assert_eq!(clicks.total(), 5);
+    }
+}
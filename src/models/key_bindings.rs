@@ -0,0 +1,187 @@
+use crate::models::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const KEY_BINDINGS_KEY: &str = "minesweeper_rs.key_bindings";
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub enum KeyAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Reveal,
+    Flag,
+    Chord,
+    PreviousLayer,
+    NextLayer,
+    Reset,
+}
+
+impl KeyAction {
+    pub const ALL: [KeyAction; 10] = [
+        KeyAction::Up,
+        KeyAction::Down,
+        KeyAction::Left,
+        KeyAction::Right,
+        KeyAction::Reveal,
+        KeyAction::Flag,
+        KeyAction::Chord,
+        KeyAction::PreviousLayer,
+        KeyAction::NextLayer,
+        KeyAction::Reset,
+    ];
+
+    pub fn title(&self) -> String {
+        match self {
+            KeyAction::Up => { "Up".into() },
+            KeyAction::Down => { "Down".into() },
+            KeyAction::Left => { "Left".into() },
+            KeyAction::Right => { "Right".into() },
+            KeyAction::Reveal => { "Reveal".into() },
+            KeyAction::Flag => { "Flag".into() },
+            KeyAction::Chord => { "Chord".into() },
+            KeyAction::PreviousLayer => { "Previous layer".into() },
+            KeyAction::NextLayer => { "Next layer".into() },
+            KeyAction::Reset => { "New game".into() },
+        }
+    }
+
+    // The arrow keys always move the cursor, whatever else movement is bound to
+    fn from_arrow_key(key: &str) -> Option<Self> {
+        match key {
+            "ArrowUp" => Some(KeyAction::Up),
+            "ArrowDown" => Some(KeyAction::Down),
+            "ArrowLeft" => Some(KeyAction::Left),
+            "ArrowRight" => Some(KeyAction::Right),
+            _ => None,
+        }
+    }
+}
+
+/// Which key triggers each [`KeyAction`], named as in the DOM's `KeyboardEvent.key`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct KeyBindings {
+    keys: BTreeMap<KeyAction, String>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = [
+            (KeyAction::Up, "k"),
+            (KeyAction::Down, "j"),
+            (KeyAction::Left, "h"),
+            (KeyAction::Right, "l"),
+            (KeyAction::Reveal, " "),
+            (KeyAction::Flag, "f"),
+            (KeyAction::Chord, "d"),
+            (KeyAction::PreviousLayer, "PageUp"),
+            (KeyAction::NextLayer, "PageDown"),
+            (KeyAction::Reset, "r"),
+        ];
+        KeyBindings { keys: keys.into_iter().map(|(action, key)| (action, key.to_string())).collect() }
+    }
+}
+
+impl KeyBindings {
+    /// The saved bindings, with any action missing from them left on its default key.
+    pub fn load(storage: &impl Storage) -> Self {
+        let mut key_bindings = KeyBindings::default();
+        if let Some(saved) = storage.load::<KeyBindings>(KEY_BINDINGS_KEY) {
+            key_bindings.keys.extend(saved.keys);
+        }
+        key_bindings
+    }
+
+    pub fn save(&self, storage: &mut impl Storage) {
+        storage.save(KEY_BINDINGS_KEY, self);
+    }
+
+    pub fn key(&self, action: KeyAction) -> &str {
+        self.keys.get(&action).map_or("", |key| key.as_str())
+    }
+
+    /// A readable name for the key bound to `action`.
+    pub fn key_title(&self, action: KeyAction) -> String {
+        match self.key(action) {
+            " " => "Space".into(),
+            key if key.chars().count() == 1 => key.to_uppercase(),
+            key => key.into(),
+        }
+    }
+
+    pub fn action(&self, key: &str) -> Option<KeyAction> {
+        self.keys
+            .iter()
+            .find(|(_, bound)| bound.eq_ignore_ascii_case(key))
+            .map(|(action, _)| *action)
+            .or_else(|| KeyAction::from_arrow_key(key))
+    }
+
+    /// Binds `key` to `action`, handing the action's old key to whichever action had `key` so no key does two things.
+    pub fn set_key(&mut self, action: KeyAction, key: &str) {
+        let old_key = self.key(action).to_string();
+        if let Some(other) = self.keys.values_mut().find(|bound| bound.eq_ignore_ascii_case(key)) {
+            *other = old_key;
+        }
+        self.keys.insert(action, key.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::storage::MemoryStorage;
+
+    #[test]
+    fn binding_a_taken_key_swaps_it_with_the_other_action() {
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.set_key(KeyAction::Flag, "D");
+
+        assert_eq!(key_bindings.key(KeyAction::Flag), "D");
+        assert_eq!(key_bindings.key(KeyAction::Chord), "f");
+        assert_eq!(key_bindings.action("d"), Some(KeyAction::Flag));
+        assert_eq!(key_bindings.action("f"), Some(KeyAction::Chord));
+    }
+
+    #[test]
+    fn loading_keeps_defaults_for_actions_missing_from_the_save() {
+        let mut storage = MemoryStorage::default();
+        storage.set(KEY_BINDINGS_KEY, r#"{"keys":{"Flag":"x"}}"#);
+
+        let key_bindings = KeyBindings::load(&storage);
+        assert_eq!(key_bindings.key(KeyAction::Flag), "x");
+        assert_eq!(key_bindings.key(KeyAction::Reveal), " ");
+        assert_eq!(key_bindings.key(KeyAction::NextLayer), "PageDown");
+    }
+
+    #[test]
+    fn saved_bindings_load_back() {
+        let mut storage = MemoryStorage::default();
+        let mut key_bindings = KeyBindings::default();
+        key_bindings.set_key(KeyAction::Reset, "n");
+        key_bindings.save(&mut storage);
+
+        assert_eq!(KeyBindings::load(&storage), key_bindings);
+    }
+
+    #[test]
+    fn arrow_keys_move_the_cursor_unless_bound_to_something_else() {
+        let mut key_bindings = KeyBindings::default();
+        assert_eq!(key_bindings.action("ArrowUp"), Some(KeyAction::Up));
+        assert_eq!(key_bindings.action("ArrowRight"), Some(KeyAction::Right));
+
+        key_bindings.set_key(KeyAction::Reveal, "ArrowDown");
+        assert_eq!(key_bindings.action("ArrowDown"), Some(KeyAction::Reveal));
+        assert_eq!(key_bindings.action("q"), None);
+    }
+
+    #[test]
+    fn key_titles_are_readable() {
+        let key_bindings = KeyBindings::default();
+
+        assert_eq!(key_bindings.key_title(KeyAction::Reveal), "Space");
+        assert_eq!(key_bindings.key_title(KeyAction::Flag), "F");
+        assert_eq!(key_bindings.key_title(KeyAction::PreviousLayer), "PageUp");
+    }
+}
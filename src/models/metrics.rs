@@ -0,0 +1,222 @@
+use crate::models::game::Game;
+use serde::{Deserialize, Serialize};
+
+/// Every click the player made, whether or not it changed the board.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct ClickCounts {
+    left: usize,
+    right: usize,
+    chord: usize,
+}
+
+impl ClickCounts {
+    pub fn left(&self) -> usize {
+        self.left
+    }
+
+    pub fn right(&self) -> usize {
+        self.right
+    }
+
+    pub fn chord(&self) -> usize {
+        self.chord
+    }
+
+    pub fn total(&self) -> usize {
+        self.left + self.right + self.chord
+    }
+
+    pub fn add_left(&mut self) {
+        self.left += 1;
+    }
+
+    pub fn add_right(&mut self) {
+        self.right += 1;
+    }
+
+    pub fn add_chord(&mut self) {
+        self.chord += 1;
+    }
+}
+
+/// The standard competitive measures of a finished game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameSummary {
+    three_bv: usize,
+    solved_three_bv: usize,
+    milliseconds: u64,
+    clicks: ClickCounts,
+}
+
+impl GameSummary {
+    pub fn new(game: &Game, clicks: ClickCounts, milliseconds: u64) -> Self {
+        let (three_bv, solved_three_bv) = three_bv(game);
+        GameSummary { three_bv, solved_three_bv, milliseconds, clicks }
+    }
+
+    /// The fewest clicks that clear the board without flagging: one per opening, plus one per number not next to one.
+    pub fn three_bv(&self) -> usize {
+        self.three_bv
+    }
+
+    /// How much of the 3BV the player cleared, which is all of it after a win.
+    pub fn solved_three_bv(&self) -> usize {
+        self.solved_three_bv
+    }
+
+    pub fn milliseconds(&self) -> u64 {
+        self.milliseconds
+    }
+
+    pub fn clicks(&self) -> ClickCounts {
+        self.clicks
+    }
+
+    pub fn three_bv_per_second(&self) -> f64 {
+        if self.milliseconds == 0 { return 0.0; }
+        self.solved_three_bv as f64 * 1000.0 / self.milliseconds as f64
+    }
+
+    /// Cleared 3BV per click; a perfect game without flags scores 1.
+    pub fn ioe(&self) -> f64 {
+        if self.clicks.total() == 0 { return 0.0; }
+        self.solved_three_bv as f64 / self.clicks.total() as f64
+    }
+
+    /// The IOE as a percentage, which is how most leaderboards quote it.
+    pub fn efficiency(&self) -> f64 {
+        self.ioe() * 100.0
+    }
+}
+
+/// The board's 3BV alongside how much of it has been cleared. Both are 0 before the mines are laid.
+pub fn three_bv(game: &Game) -> (usize, usize) {
+    if !game.has_started() && game.mine_indices().is_empty() { return (0, 0); }
+
+    let cells = game.cells();
+    let mut counted = vec![false; cells.len()];
+    let openings = fill_openings(game, &mut counted);
+    let mut total = openings.len();
+    let mut solved = openings.iter().filter(|opening_is_shown| **opening_is_shown).count();
+
+    for (index, cell) in cells.iter().enumerate() {
+        if counted[index] || cell.is_mine() { continue; }
+        total += 1;
+        if cell.is_shown() { solved += 1; }
+    }
+
+    (total, solved)
+}
+
+/// How many separate openings the board has, each cleared by a single click. 0 before the mines are laid.
+pub fn openings(game: &Game) -> usize {
+    if !game.has_started() && game.mine_indices().is_empty() { return 0; }
+    fill_openings(game, &mut vec![false; game.cells().len()]).len()
+}
+
+// Marks every opening and the numbers around it in `counted`, returning whether each opening has been shown
+fn fill_openings(game: &Game, counted: &mut [bool]) -> Vec<bool> {
+    let cells = game.cells();
+    let mut openings = Vec::new();
+
+    // Each opening is one click however big it is, and takes the numbers around it along
+    for start in 0..cells.len() {
+        if counted[start] || !cells[start].is_zero() { continue; }
+
+        let mut opening_is_shown = false;
+        let mut stack = vec![start];
+        counted[start] = true;
+        while let Some(index) = stack.pop() {
+            opening_is_shown |= cells[index].is_shown();
+            for neighbor in game.neighbors(index) {
+                if counted[*neighbor] { continue; }
+                counted[*neighbor] = true;
+                if cells[*neighbor].is_zero() { stack.push(*neighbor); }
+            }
+        }
+        openings.push(opening_is_shown);
+    }
+    openings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::test_board;
+
+    fn clicks(left: usize, right: usize, chord: usize) -> ClickCounts {
+        ClickCounts { left, right, chord }
+    }
+
+    #[test]
+    fn an_opening_and_the_numbers_around_it_count_once() {
+        // * 1 . . .
+        let game = test_board(5, 1, &[0], &[]);
+
+        assert_eq!(three_bv(&game), (1, 0));
+        assert_eq!(openings(&game), 1);
+    }
+
+    #[test]
+    fn numbers_away_from_openings_count_one_each() {
+        // 1 * 2 * 1
+        let game = test_board(5, 1, &[1, 3], &[]);
+
+        assert_eq!(three_bv(&game), (3, 0));
+        assert_eq!(openings(&game), 0);
+    }
+
+    #[test]
+    fn openings_and_lone_numbers_add_up() {
+        // * 2 * 1 . 1 * 1 .
+        let game = test_board(9, 1, &[0, 2, 6], &[]);
+
+        assert_eq!(three_bv(&game), (3, 0));
+        assert_eq!(openings(&game), 2);
+    }
+
+    #[test]
+    fn a_lost_game_only_solves_what_was_shown() {
+        // The lone 2 and the opening on the right are cleared; the number on the edge of the middle opening doesn't clear it
+        let game = test_board(9, 1, &[0, 2, 6], &[1, 3, 8, 2]);
+        assert!(game.is_lost());
+
+        let summary = GameSummary::new(&game, clicks(4, 0, 0), 2000);
+        assert_eq!(summary.three_bv(), 3);
+        assert_eq!(summary.solved_three_bv(), 2);
+        assert_eq!(summary.three_bv_per_second(), 1.0);
+        assert_eq!(summary.ioe(), 0.5);
+        assert_eq!(summary.efficiency(), 50.0);
+    }
+
+    #[test]
+    fn a_won_game_solves_all_of_its_three_bv() {
+        let game = test_board(9, 1, &[0, 2, 6], &[1, 4, 8]);
+        assert!(game.is_won());
+
+        let summary = GameSummary::new(&game, clicks(3, 1, 1), 1500);
+        assert_eq!((summary.three_bv(), summary.solved_three_bv()), (3, 3));
+        assert_eq!(summary.three_bv_per_second(), 2.0);
+        assert_eq!(summary.clicks().total(), 5);
+        assert_eq!(summary.ioe(), 0.6);
+    }
+
+    #[test]
+    fn rates_are_zero_without_clicks_or_time() {
+        let game = test_board(5, 1, &[0], &[2]);
+        let summary = GameSummary::new(&game, ClickCounts::default(), 0);
+
+        assert_eq!(summary.solved_three_bv(), 1);
+        assert_eq!(summary.three_bv_per_second(), 0.0);
+        assert_eq!(summary.ioe(), 0.0);
+        assert_eq!(summary.efficiency(), 0.0);
+    }
+
+    #[test]
+    fn boards_without_mines_laid_have_no_three_bv() {
+        let game = Game::default();
+
+        assert_eq!(three_bv(&game), (0, 0));
+        assert_eq!(openings(&game), 0);
+    }
+}
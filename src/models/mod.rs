@@ -1,4 +1,20 @@
+pub mod board_code;
 pub mod cell;
 pub mod face;
+pub mod game;
+pub mod history;
+pub mod key_bindings;
+pub mod metrics;
 pub mod mouse_state;
-pub mod settings;
\ No newline at end of file
+pub mod neighborhood;
+pub mod probability;
+pub mod replay;
+pub mod saved_game;
+pub mod settings;
+pub mod simulation;
+pub mod solver;
+pub mod stats;
+pub mod stopwatch;
+pub mod storage;
+pub mod topology;
+pub mod touch_state;
\ No newline at end of file
@@ -1,4 +1,3 @@
-use web_sys::MouseEvent;
 use crate::models::settings::ChordSetting;
 // use gloo_console as console;
 
@@ -32,6 +31,7 @@ pub enum MouseState {
     Right,
     Both,
     AfterBoth,
+    Middle,
 }
 
 impl MouseState {
@@ -45,20 +45,23 @@ impl MouseState {
 
     pub fn is_chording(self, chord_setting: ChordSetting, cell_is_shown: bool) -> bool{
         match self {
-            Self::Left => { cell_is_shown && chord_setting == ChordSetting::LeftClick },
-            Self::Both => { true },
+            Self::Left => { cell_is_shown && chord_setting.chords_on_left_click() },
+            Self::Both => { chord_setting != ChordSetting::Disabled },
+            Self::Middle => { chord_setting.chords_on_middle_click() },
             _ => { false },
         }
     }
 
-    pub fn mouse_down(&self, event: MouseEvent) -> Self {
-        let button = event.button().to_mouse_button();
+    /// `button` is the DOM `MouseEvent.button` code of the pressed button.
+    pub fn mouse_down(&self, button: i16) -> Self {
+        let button = button.to_mouse_button();
         match self {
             Self::Neither => {
                 match button {
                     MouseButton::Left => { Self::Left },
+                    MouseButton::Middle => { Self::Middle },
                     MouseButton::Right => { Self::Right },
-                    _ => { Self::Neither },
+                    MouseButton::Other => { Self::Neither },
                 }
             },
             Self::Left => {
@@ -74,11 +77,13 @@ impl MouseState {
                 }
             },
             Self::Both | Self::AfterBoth => { Self::Both },
+            Self::Middle => { Self::Middle },
         }
     }
 
-    pub fn mouse_up(&self, event: MouseEvent) -> Self {
-        let button = event.button().to_mouse_button();
+    /// `button` is the DOM `MouseEvent.button` code of the released button.
+    pub fn mouse_up(&self, button: i16) -> Self {
+        let button = button.to_mouse_button();
         match self {
             Self::Both => {
                 match button {
@@ -99,7 +104,35 @@ impl MouseState {
                     _ => { Self::Right },
                 }
             },
+            Self::Middle => {
+                match button {
+                    MouseButton::Middle => { Self::Neither },
+                    _ => { Self::Middle },
+                }
+            },
             Self::Neither => { Self::Neither },
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_or_middle_click_chords_with_either_button() {
+        let chord_setting = ChordSetting::LeftOrMiddleClick;
+        assert!(MouseState::Left.is_chording(chord_setting, true));
+        assert!(!MouseState::Left.is_chording(chord_setting, false));
+        assert!(MouseState::Middle.is_chording(chord_setting, false));
+        assert!(MouseState::Both.is_chording(chord_setting, false));
+    }
+
+    #[test]
+    fn each_button_only_chords_when_chosen() {
+        assert!(!MouseState::Left.is_chording(ChordSetting::MiddleClick, true));
+        assert!(!MouseState::Middle.is_chording(ChordSetting::LeftClick, true));
+        assert!(!MouseState::Both.is_chording(ChordSetting::Disabled, true));
+        assert!(!MouseState::Right.is_chording(ChordSetting::LeftOrMiddleClick, true));
+    }
+}
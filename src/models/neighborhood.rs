@@ -0,0 +1,193 @@
+use crate::models::topology::Topology;
+use serde::{Deserialize, Serialize};
+
+/// How many rows and columns away a neighbor can be, which bounds every neighborhood.
+pub const MAX_REACH: isize = 2;
+const MASK_SIDE: isize = (2 * MAX_REACH) + 1;
+
+const VON_NEUMANN_OFFSETS: [(isize, isize); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+const KNIGHT_OFFSETS: [(isize, isize); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+
+/// Any set of `(row, column)` offsets within [`MAX_REACH`] of a cell, a bit for each square of the 5x5 grid around it.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct NeighborMask(u32);
+
+impl Default for NeighborMask {
+    fn default() -> Self { Neighborhood::Moore.mask() }
+}
+
+impl NeighborMask {
+    /// The mask with these raw bits, if they stay within the grid, leave out the cell itself and
+    /// include at least one neighbor.
+    pub fn from_bits(bits: u32) -> Option<Self> {
+        let center = 1 << ((MAX_REACH * MASK_SIDE) + MAX_REACH);
+        let in_grid = bits >> (MASK_SIDE * MASK_SIDE) == 0;
+        (bits != 0 && in_grid && bits & center == 0).then_some(NeighborMask(bits))
+    }
+
+    /// Offsets out of reach and the cell itself are left out.
+    pub fn from_offsets(offsets: &[(isize, isize)]) -> Self {
+        offsets.iter().fold(NeighborMask(0), |mask, offset| mask.with(*offset, true))
+    }
+
+    pub fn contains(&self, offset: (isize, isize)) -> bool {
+        NeighborMask::bit(offset).map_or(false, |bit| self.0 & bit != 0)
+    }
+
+    /// The mask with `offset` switched, unless that would leave no neighbors at all.
+    pub fn toggled(self, offset: (isize, isize)) -> Self {
+        let toggled = self.with(offset, !self.contains(offset));
+        if toggled.0 == 0 { self } else { toggled }
+    }
+
+    /// Row by row, so the offsets come out in reading order.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        NeighborMask::grid().filter(|offset| self.contains(*offset)).collect()
+    }
+
+    /// Every square of the 5x5 grid in reading order, including the cell itself in the middle.
+    pub fn grid() -> impl Iterator<Item = (isize, isize)> {
+        (-MAX_REACH..=MAX_REACH).flat_map(|row| (-MAX_REACH..=MAX_REACH).map(move |col| (row, col)))
+    }
+
+    // Private methods
+    fn with(self, offset: (isize, isize), on: bool) -> Self {
+        let Some(bit) = NeighborMask::bit(offset) else { return self; };
+        NeighborMask(if on { self.0 | bit } else { self.0 & !bit })
+    }
+
+    fn bit(offset: (isize, isize)) -> Option<u32> {
+        let (row, col) = offset;
+        if offset == (0, 0) || row.abs() > MAX_REACH || col.abs() > MAX_REACH { return None; }
+        Some(1 << (((row + MAX_REACH) * MASK_SIDE) + col + MAX_REACH))
+    }
+}
+
+/// Which cells count towards a square cell's number.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Neighborhood {
+    /// The eight surrounding cells of the classic game.
+    #[default]
+    Moore,
+    /// Only the four cells sharing a side.
+    VonNeumann,
+    /// The eight cells a chess knight could jump to.
+    Knight,
+    /// Everything up to two cells away in any direction.
+    Radius2,
+    Custom(NeighborMask),
+}
+
+impl Neighborhood {
+    pub fn title(&self) -> String {
+        match self {
+            Neighborhood::Moore => { "Surrounding (8)".into() },
+            Neighborhood::VonNeumann => { "Cross (4)".into() },
+            Neighborhood::Knight => { "Knight's move (8)".into() },
+            Neighborhood::Radius2 => { "Radius 2 (24)".into() },
+            Neighborhood::Custom(_) => { "Custom".into() },
+        }
+    }
+
+    /// A short name for the stats and variant labels, or `None` for the classic neighborhood.
+    pub fn name(&self) -> Option<String> {
+        match self {
+            Neighborhood::Moore => { None },
+            Neighborhood::VonNeumann => { Some("cross".into()) },
+            Neighborhood::Knight => { Some("knight".into()) },
+            Neighborhood::Radius2 => { Some("radius2".into()) },
+            Neighborhood::Custom(mask) => { Some(format!("mask{:x}", mask.0)) },
+        }
+    }
+
+    /// The neighborhood [`name`](Neighborhood::name) gave, or `None` for a name it never gives.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cross" => { Some(Neighborhood::VonNeumann) },
+            "knight" => { Some(Neighborhood::Knight) },
+            "radius2" => { Some(Neighborhood::Radius2) },
+            _ => {
+                let bits = u32::from_str_radix(name.strip_prefix("mask")?, 16).ok()?;
+                NeighborMask::from_bits(bits).map(Neighborhood::Custom)
+            },
+        }
+    }
+
+    pub fn mask(&self) -> NeighborMask {
+        match self {
+            Neighborhood::Moore => { NeighborMask::from_offsets(Topology::Square.neighbor_offsets(0)) },
+            Neighborhood::VonNeumann => { NeighborMask::from_offsets(&VON_NEUMANN_OFFSETS) },
+            Neighborhood::Knight => { NeighborMask::from_offsets(&KNIGHT_OFFSETS) },
+            Neighborhood::Radius2 => { NeighborMask::from_offsets(&NeighborMask::grid().collect::<Vec<_>>()) },
+            Neighborhood::Custom(mask) => { *mask },
+        }
+    }
+
+    /// The `(row, column)` offsets from a cell to each of its neighbors.
+    pub fn offsets(&self) -> Vec<(isize, isize)> {
+        self.mask().offsets()
+    }
+
+    /// How many rows or columns away the farthest neighbor is.
+    pub fn reach(&self) -> usize {
+        self.offsets().iter().map(|(row, col)| row.unsigned_abs().max(col.unsigned_abs())).max().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_neighborhood_has_its_own_offsets() {
+        assert_eq!(Neighborhood::Moore.offsets().len(), 8);
+        assert_eq!(Neighborhood::VonNeumann.offsets(), vec![(-1, 0), (0, -1), (0, 1), (1, 0)]);
+        assert_eq!(Neighborhood::Knight.offsets().len(), 8);
+        assert!(!Neighborhood::Knight.offsets().contains(&(1, 1)));
+        assert_eq!(Neighborhood::Radius2.offsets().len(), 24);
+    }
+
+    #[test]
+    fn reach_is_the_farthest_neighbor() {
+        assert_eq!(Neighborhood::Moore.reach(), 1);
+        assert_eq!(Neighborhood::VonNeumann.reach(), 1);
+        assert_eq!(Neighborhood::Knight.reach(), 2);
+        assert_eq!(Neighborhood::Custom(NeighborMask::from_offsets(&[(0, 2)])).reach(), 2);
+    }
+
+    #[test]
+    fn masks_leave_out_the_cell_itself_and_anything_out_of_reach() {
+        let mask = NeighborMask::from_offsets(&[(0, 0), (0, 3), (-2, 2), (1, 0)]);
+
+        assert_eq!(mask.offsets(), vec![(-2, 2), (1, 0)]);
+        assert!(!mask.contains((0, 3)));
+    }
+
+    #[test]
+    fn toggling_never_leaves_a_mask_empty() {
+        let mask = NeighborMask::from_offsets(&[(1, 0)]);
+
+        assert_eq!(mask.toggled((1, 0)), mask);
+        assert_eq!(mask.toggled((0, 1)).offsets(), vec![(0, 1), (1, 0)]);
+        assert_eq!(mask.toggled((0, 1)).toggled((0, 1)), mask);
+    }
+
+    #[test]
+    fn names_read_back_as_the_same_neighborhood() {
+        let custom = Neighborhood::Custom(NeighborMask::from_offsets(&[(-2, 0), (2, 0), (0, -1)]));
+        for neighborhood in [Neighborhood::VonNeumann, Neighborhood::Knight, Neighborhood::Radius2, custom] {
+            let name = neighborhood.name().expect("only the classic neighborhood goes unnamed");
+            assert_eq!(Neighborhood::from_name(&name), Some(neighborhood));
+        }
+        assert_eq!(Neighborhood::Moore.name(), None);
+    }
+
+    #[test]
+    fn names_for_impossible_masks_are_rejected() {
+        assert_eq!(Neighborhood::from_name("mask0"), None);
+        assert_eq!(Neighborhood::from_name("maskzz"), None);
+        assert_eq!(Neighborhood::from_name("mask1000"), None);
+        assert_eq!(Neighborhood::from_name("mask2000000"), None);
+        assert_eq!(Neighborhood::from_name("hex"), None);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::models::game::Game;
+use crate::models::solver::Solver;
+use std::collections::HashSet;
+
+/// The chance that each cell is a mine given everything the player can see, or `None` for shown cells.
+///
+/// Every mine arrangement consistent with the shown numbers and the number of mines left is
+/// weighted equally. Cells away from the numbers share whatever mines the numbered groups leave
+/// over. Groups too large to enumerate are treated like those cells, so the odds there are only
+/// approximate. Flags are ignored.
+pub fn mine_probabilities(game: &Game) -> Vec<Option<f64>> {
+    let mut probabilities: Vec<Option<f64>> = game.cells()
+        .iter()
+        .map(|cell| if cell.is_shown() { None } else { Some(0.0) })
+        .collect();
+    if !game.has_started() {
+        let chance = game.mines_count() as f64 / game.cells().len() as f64;
+        return probabilities.iter().map(|_| Some(chance)).collect();
+    }
+
+    let mut solver = Solver::new(game);
+    solver.deduce();
+    for index in solver.known_mines() {
+        probabilities[*index] = Some(1.0);
+    }
+
+    let enumerations = solver.enumerations();
+    let enumerated: HashSet<usize> = enumerations.iter().flat_map(|enumeration| enumeration.cells().iter().copied()).collect();
+    let others: Vec<usize> = solver.unknown_cells().into_iter().filter(|index| !enumerated.contains(index)).collect();
+    let mines_left = game.mines_count().saturating_sub(solver.known_mines().len());
+
+    // Arrangement counts per group, indexed by how many mines the group holds
+    let distributions: Vec<Vec<f64>> = enumerations
+        .iter()
+        .map(|enumeration| {
+            let max_mines = enumeration.by_mine_count().keys().last().copied().unwrap_or(0);
+            let mut distribution = vec![0.0; max_mines + 1];
+            for (mines, (count, _)) in enumeration.by_mine_count() {
+                distribution[*mines] = *count as f64;
+            }
+            distribution
+        })
+        .collect();
+
+    let ln_factorials = ln_factorials(game.cells().len());
+    // Ways to place the leftover mines among the other cells, scaled to avoid overflow
+    let leftover_weights: Vec<f64> = {
+        let ln_weights: Vec<f64> = (0..=mines_left)
+            .map(|frontier_mines| {
+                let leftover = mines_left - frontier_mines;
+                if leftover > others.len() { f64::NEG_INFINITY } else { ln_choose(&ln_factorials, others.len(), leftover) }
+            })
+            .collect();
+        let max = ln_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        ln_weights.iter().map(|ln_weight| (ln_weight - max).exp()).collect()
+    };
+
+    let everything = distributions.iter().fold(vec![1.0], |total, distribution| convolve(&total, distribution));
+    let total_weight: f64 = everything.iter().enumerate().map(|(mines, count)| count * weight(&leftover_weights, mines)).sum();
+    if total_weight == 0.0 { return probabilities; }
+
+    for (g, enumeration) in enumerations.iter().enumerate() {
+        let rest = distributions
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != g)
+            .fold(vec![1.0], |total, (_, distribution)| convolve(&total, distribution));
+
+        for (i, cell) in enumeration.cells().iter().enumerate() {
+            let mut cell_weight = 0.0;
+            for (group_mines, (_, cell_mines)) in enumeration.by_mine_count() {
+                for (rest_mines, rest_count) in rest.iter().enumerate() {
+                    cell_weight += cell_mines[i] as f64 * rest_count * weight(&leftover_weights, group_mines + rest_mines);
+                }
+            }
+            probabilities[*cell] = Some(cell_weight / total_weight);
+        }
+    }
+
+    if !others.is_empty() {
+        let other_weight: f64 = everything
+            .iter()
+            .enumerate()
+            .filter(|(mines, _)| *mines <= mines_left)
+            .map(|(mines, count)| count * weight(&leftover_weights, mines) * (mines_left - mines) as f64 / others.len() as f64)
+            .sum();
+        for index in others {
+            probabilities[index] = Some(other_weight / total_weight);
+        }
+    }
+
+    probabilities
+}
+
+fn weight(leftover_weights: &[f64], frontier_mines: usize) -> f64 {
+    leftover_weights.get(frontier_mines).copied().unwrap_or(0.0)
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut result = vec![0.0; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    result
+}
+
+fn ln_factorials(n: usize) -> Vec<f64> {
+    let mut ln_factorials = vec![0.0; n + 1];
+    for i in 1..=n {
+        ln_factorials[i] = ln_factorials[i - 1] + (i as f64).ln();
+    }
+    ln_factorials
+}
+
+fn ln_choose(ln_factorials: &[f64], n: usize, k: usize) -> f64 {
+    ln_factorials[n] - ln_factorials[k] - ln_factorials[n - k]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::test_board;
+    use crate::models::settings::Settings;
+
+    fn assert_odds(probabilities: &[Option<f64>], expected: &[Option<f64>]) {
+        assert_eq!(probabilities.len(), expected.len());
+        for (index, (probability, expected)) in probabilities.iter().zip(expected).enumerate() {
+            match (probability, expected) {
+                (Some(probability), Some(expected)) => {
+                    assert!((probability - expected).abs() < 1e-9, "cell {}: {} but expected {}", index, probability, expected);
+                },
+                _ => assert_eq!(probability, expected, "cell {}", index),
+            }
+        }
+    }
+
+    #[test]
+    fn every_cell_is_alike_before_the_first_click() {
+        let game = Game::new(Settings::default());
+        let probabilities = mine_probabilities(&game);
+
+        assert!(probabilities.iter().all(|probability| *probability == Some(10.0 / 81.0)));
+    }
+
+    #[test]
+    fn known_cells_are_certain() {
+        // 2 ? ?
+        // * * 1
+        // Only {1, 3} and {3, 4} fit, so 3 is a mine, 2 is safe and 1 and 4 split the other mine
+        let game = test_board(3, 2, &[3, 4], &[0, 5]);
+
+        assert_odds(&mine_probabilities(&game), &[None, Some(0.5), Some(0.0), Some(1.0), Some(0.5), None]);
+    }
+
+    // a 1 b 1 c o o o: the group holds either b alone or both a and c, and the three cells
+    // on the right take whatever mines are left
+    #[test]
+    fn the_mines_left_weigh_the_arrangements() {
+        // With two mines, b alone leaves one for three cells (3 ways) and a and c leave none (1 way)
+        let game = test_board(8, 1, &[2, 6], &[1, 3]);
+        let (b, a_c, other) = (Some(3.0 / 4.0), Some(1.0 / 4.0), Some(1.0 / 4.0));
+        assert_odds(&mine_probabilities(&game), &[a_c, None, b, None, a_c, other, other, other]);
+
+        // A third mine makes the two arrangements equally likely: 3 ways each
+        let game = test_board(8, 1, &[2, 5, 6], &[1, 3]);
+        let half = Some(0.5);
+        assert_odds(&mine_probabilities(&game), &[half, None, half, None, half, half, half, half]);
+    }
+
+    #[test]
+    fn separate_groups_are_combined() {
+        // a 1 b 1 c o d 1 e o, with three mines: d and e always hold one, so the first group's
+        // arrangements weigh 1 x 2 x C(2, 1) = 4 for b and 1 x 2 x C(2, 0) = 2 for a and c
+        let game = test_board(10, 1, &[2, 6, 9], &[1, 3, 7]);
+        let (b, a_c, d_e, other) = (Some(4.0 / 6.0), Some(2.0 / 6.0), Some(0.5), Some(2.0 / 6.0));
+
+        assert_odds(&mine_probabilities(&game), &[a_c, None, b, None, a_c, other, d_e, None, d_e, other]);
+    }
+}
@@ -0,0 +1,342 @@
+use crate::models::board_code::{BoardCode, BoardCodeError};
+use crate::models::cell::Cell;
+use crate::models::game::{Game, LoadBoardError};
+use crate::models::settings::{FirstClickSetting, Settings};
+use serde::{Deserialize, Serialize};
+
+const REPLAY_SEPARATOR: char = '~';
+const ACTION_SEPARATOR: char = ',';
+const TIME_SEPARATOR: char = '@';
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ActionKind {
+    Reveal,
+    Flag,
+    Unflag,
+    /// The `?` mark, which right clicks go through when the settings allow it.
+    Mark,
+    Chord,
+}
+
+impl ActionKind {
+    /// The action that leaves a cell with the marks `cell` has now, for recording a right click.
+    pub fn for_marks_on(cell: &Cell) -> Self {
+        if cell.is_flagged() {
+            ActionKind::Flag
+        } else if cell.is_marked_unknown() {
+            ActionKind::Mark
+        } else {
+            ActionKind::Unflag
+        }
+    }
+
+    fn to_char(self) -> char {
+        match self {
+            ActionKind::Reveal  => 'r',
+            ActionKind::Flag    => 'f',
+            ActionKind::Unflag  => 'u',
+            ActionKind::Mark    => 'm',
+            ActionKind::Chord   => 'c',
+        }
+    }
+
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            'r' => Some(ActionKind::Reveal),
+            'f' => Some(ActionKind::Flag),
+            'u' => Some(ActionKind::Unflag),
+            'm' => Some(ActionKind::Mark),
+            'c' => Some(ActionKind::Chord),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Action {
+    kind: ActionKind,
+    index: usize,
+    milliseconds: u64,
+}
+
+impl Action {
+    pub fn new(kind: ActionKind, index: usize, milliseconds: u64) -> Self {
+        Action { kind, index, milliseconds }
+    }
+
+    pub fn kind(&self) -> ActionKind {
+        self.kind
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Time since the first click.
+    pub fn milliseconds(&self) -> u64 {
+        self.milliseconds
+    }
+
+    /// Applies this action to `game`, returning whether it changed anything.
+    pub fn apply(&self, game: &mut Game) -> bool {
+        match self.kind {
+            ActionKind::Reveal  => game.reveal(self.index),
+            ActionKind::Flag    => game.add_flag(self.index),
+            ActionKind::Unflag  => game.set_flagged(self.index, false),
+            ActionKind::Mark    => game.mark_unknown(self.index),
+            ActionKind::Chord   => game.chord(self.index),
+        }
+    }
+}
+
+/// Every state-changing action of one game, alongside the mine layout it was played on.
+///
+/// Encoded as `BOARD~ACTION,ACTION,...` where `BOARD` is a [`BoardCode`] and each action is its
+/// kind (`r`eveal, `f`lag, `u`nflag, `m`ark or `c`hord), the cell index, `@` and the milliseconds since the first click.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Replay {
+    board_code: BoardCode,
+    actions: Vec<Action>,
+}
+
+impl Replay {
+    /// Starts a recording on the layout of `board_code`. Its first click is ignored, since it'll be recorded as an action.
+    pub fn new(board_code: &BoardCode) -> Self {
+        Replay { board_code: board_code.clone().without_first_click(), actions: Vec::new() }
+    }
+
+    pub fn board_code(&self) -> &BoardCode {
+        &self.board_code
+    }
+
+    pub fn actions(&self) -> &[Action] {
+        &self.actions
+    }
+
+    pub fn record(&mut self, kind: ActionKind, index: usize, milliseconds: u64) {
+        self.actions.push(Action::new(kind, index, milliseconds));
+    }
+
+    pub fn duration_milliseconds(&self) -> u64 {
+        self.actions.last().map(|action| action.milliseconds).unwrap_or(0)
+    }
+
+    /// A fresh game on this replay's layout with none of the actions applied yet, on the grid the
+    /// code gives rather than the viewer's.
+    pub fn new_game(&self) -> Result<Game, LoadBoardError> {
+        // The mines are laid out already, so use the first click rule that accepts any layout
+        let mut settings = Settings::default();
+        settings.set_first_click_setting(FirstClickSetting::Any);
+        let mut game = Game::new(settings);
+        game.load_board(&self.board_code)?;
+        Ok(game)
+    }
+
+    pub fn encode(&self) -> String {
+        let actions: Vec<String> = self.actions
+            .iter()
+            .map(|action| format!("{}{}{}{}", action.kind.to_char(), action.index, TIME_SEPARATOR, action.milliseconds))
+            .collect();
+
+        format!("{}{}{}", self.board_code.encode(), REPLAY_SEPARATOR, actions.join(&ACTION_SEPARATOR.to_string()))
+    }
+
+    pub fn decode(code: &str) -> Result<Self, BoardCodeError> {
+        let (board, actions) = code.trim().split_once(REPLAY_SEPARATOR).ok_or(BoardCodeError::MissingSection)?;
+        let board_code = BoardCode::decode(board)?;
+        let cell_count = board_code.dimensions().cell_count();
+
+        let actions = actions
+            .split(ACTION_SEPARATOR)
+            .filter(|action| !action.is_empty())
+            .map(|action| {
+                let mut chars = action.chars();
+                let kind = chars.next().and_then(ActionKind::from_char).ok_or(BoardCodeError::InvalidActions)?;
+                let (index, milliseconds) = chars.as_str().split_once(TIME_SEPARATOR).ok_or(BoardCodeError::InvalidActions)?;
+                let index: usize = index.parse().map_err(|_| BoardCodeError::InvalidActions)?;
+                let milliseconds: u64 = milliseconds.parse().map_err(|_| BoardCodeError::InvalidActions)?;
+                if index >= cell_count { return Err(BoardCodeError::InvalidActions); }
+                Ok(Action::new(kind, index, milliseconds))
+            })
+            .collect::<Result<Vec<Action>, BoardCodeError>>()?;
+
+        Ok(Replay { board_code, actions })
+    }
+
+    /// Whether `code` looks like a replay rather than a bare board code.
+    pub fn is_replay_code(code: &str) -> bool {
+        code.contains(REPLAY_SEPARATOR)
+    }
+}
+
+impl From<Replay> for String {
+    fn from(replay: Replay) -> Self {
+        replay.encode()
+    }
+}
+
+impl TryFrom<String> for Replay {
+    type Error = BoardCodeError;
+
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        Replay::decode(&code)
+    }
+}
+
+/// Steps a [`Replay`] forwards and backwards on its own [`Game`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReplayPlayer {
+    replay: Replay,
+    start: Game,
+    game: Game,
+    step: usize,
+    milliseconds: u64,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Result<Self, LoadBoardError> {
+        let start = replay.new_game()?;
+        Ok(ReplayPlayer { replay, game: start.clone(), start, step: 0, milliseconds: 0 })
+    }
+
+    pub fn replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    /// How many actions have been applied.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn milliseconds(&self) -> u64 {
+        self.milliseconds
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.step == self.replay.actions.len()
+    }
+
+    /// The action that was applied last, if any.
+    pub fn last_action(&self) -> Option<&Action> {
+        self.step.checked_sub(1).map(|step| &self.replay.actions[step])
+    }
+
+    pub fn step_forward(&mut self) {
+        let Some(action) = self.replay.actions.get(self.step) else { return; };
+        action.apply(&mut self.game);
+        self.milliseconds = action.milliseconds;
+        self.step += 1;
+    }
+
+    pub fn step_back(&mut self) {
+        self.seek(self.step.saturating_sub(1));
+    }
+
+    /// Rebuilds the game with exactly the first `step` actions applied.
+    pub fn seek(&mut self, step: usize) {
+        self.game = self.start.clone();
+        self.step = 0;
+        self.milliseconds = 0;
+        while self.step < step.min(self.replay.actions.len()) {
+            self.step_forward();
+        }
+    }
+
+    /// Moves the clock on by `milliseconds`, applying every action that happened in that time.
+    pub fn advance(&mut self, milliseconds: u64) {
+        let target = self.milliseconds + milliseconds;
+        while self.replay.actions.get(self.step).map_or(false, |action| action.milliseconds <= target) {
+            self.step_forward();
+        }
+        self.milliseconds = if self.is_finished() { self.replay.duration_milliseconds() } else { target };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::test_board;
+    use crate::models::settings::Dimensions;
+    use crate::models::topology::Topology;
+
+    // `* 1 . * . . .`, where revealing the second cell shows only its own number
+    fn replay_of(actions: &[(ActionKind, usize, u64)]) -> Replay {
+        let game = test_board(7, 1, &[0, 3], &[]);
+        let mut replay = Replay::new(&game.board_code().expect("the board is laid out"));
+        for (kind, index, milliseconds) in actions {
+            replay.record(*kind, *index, *milliseconds);
+        }
+        replay
+    }
+
+    #[test]
+    fn replays_round_trip_through_their_code() {
+        let replay = replay_of(&[(ActionKind::Reveal, 1, 0), (ActionKind::Flag, 0, 250), (ActionKind::Mark, 5, 600), (ActionKind::Unflag, 5, 900), (ActionKind::Chord, 1, 1200)]);
+
+        assert!(replay.encode().ends_with("~r1@0,f0@250,m5@600,u5@900,c1@1200"));
+        assert_eq!(Replay::decode(&replay.encode()), Ok(replay));
+        assert_eq!(Replay::decode("7x1..kg~r1@0,x1@5"), Err(BoardCodeError::InvalidActions));
+        assert_eq!(Replay::decode("7x1..kg~r7@0"), Err(BoardCodeError::InvalidActions));
+    }
+
+    #[test]
+    fn replaying_right_clicks_keeps_question_marks() {
+        let mut live = test_board(7, 1, &[0, 3], &[1]);
+        let mut settings = live.settings();
+        settings.set_allow_mark_cell_as_unknown(true);
+        live.set_settings(settings);
+
+        let mut replay = replay_of(&[(ActionKind::Reveal, 1, 0)]);
+        let mut cells = Vec::new();
+        for milliseconds in 1..=5 {
+            live.toggle_flag(5);
+            replay.record(ActionKind::for_marks_on(live.cell(5)), 5, milliseconds);
+            cells.push(live.cell(5).clone());
+        }
+
+        let mut player = ReplayPlayer::new(replay).expect("the layout fits");
+        player.step_forward();
+        for cell in cells {
+            player.step_forward();
+            assert_eq!(player.game().cell(5), &cell);
+        }
+    }
+
+    #[test]
+    fn advancing_applies_the_actions_due_by_then() {
+        let replay = replay_of(&[(ActionKind::Reveal, 1, 0), (ActionKind::Flag, 0, 500), (ActionKind::Reveal, 2, 1000)]);
+        let mut player = ReplayPlayer::new(replay).expect("the layout fits");
+
+        player.advance(600);
+        assert_eq!(player.step(), 2);
+        assert_eq!(player.milliseconds(), 600);
+        assert!(player.game().cell(0).is_flagged());
+
+        player.advance(1000);
+        assert!(player.is_finished());
+        assert_eq!(player.milliseconds(), 1000);
+
+        player.seek(1);
+        assert_eq!(player.last_action().map(Action::kind), Some(ActionKind::Reveal));
+        assert!(!player.game().cell(0).is_flagged());
+        assert!(!player.game().cell(2).is_shown());
+    }
+
+    #[test]
+    fn a_replay_opens_on_its_own_grid() {
+        let board_code = BoardCode::new(Dimensions::new(4, 4, 0), vec![5], Some(15)).with_topology(Topology::Hex);
+        let mut replay = Replay::new(&board_code);
+        replay.record(ActionKind::Reveal, 15, 0);
+        let replay = Replay::decode(&replay.encode()).expect("an encoded replay decodes");
+
+        let player = ReplayPlayer::new(replay).expect("the layout fits");
+        assert_eq!(player.game().settings().topology(), Topology::Hex);
+        assert_eq!(player.game().neighbors(5).len(), 6);
+        assert_eq!(player.replay().board_code().first_click_index(), None);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::models::game::Game;
+use crate::models::metrics::ClickCounts;
+use crate::models::replay::Replay;
+use crate::models::storage::Storage;
+use serde::{Deserialize, Serialize};
+
+const SAVED_GAME_KEY: &str = "minesweeper_rs.saved_game";
+
+/// A game in progress, kept so it can be picked up again after the page is closed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct SavedGame {
+    game: Game,
+    elapsed_milliseconds: u64,
+    replay: Option<Replay>,
+    #[serde(default)]
+    unranked: bool,
+    #[serde(default)]
+    clicks: ClickCounts,
+}
+
+impl SavedGame {
+    pub fn new(game: Game, elapsed_milliseconds: u64, replay: Option<Replay>, unranked: bool, clicks: ClickCounts) -> Self {
+        SavedGame { game, elapsed_milliseconds, replay, unranked, clicks }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn elapsed_milliseconds(&self) -> u64 {
+        self.elapsed_milliseconds
+    }
+
+    pub fn replay(&self) -> Option<&Replay> {
+        self.replay.as_ref()
+    }
+
+    /// Whether the game was played in practice mode, so it's kept out of the stats.
+    pub fn is_unranked(&self) -> bool {
+        self.unranked
+    }
+
+    pub fn clicks(&self) -> ClickCounts {
+        self.clicks
+    }
+
+    pub fn into_parts(self) -> (Game, u64, Option<Replay>, bool, ClickCounts) {
+        (self.game, self.elapsed_milliseconds, self.replay, self.unranked, self.clicks)
+    }
+
+    /// The saved game, if there's one that's still being played.
+    pub fn load(storage: &impl Storage) -> Option<Self> {
+        storage.load::<SavedGame>(SAVED_GAME_KEY).filter(|saved_game| saved_game.game.is_active() && saved_game.game.has_started())
+    }
+
+    pub fn save(&self, storage: &mut impl Storage) {
+        storage.save(SAVED_GAME_KEY, self);
+    }
+
+    pub fn clear(storage: &mut impl Storage) {
+        storage.remove(SAVED_GAME_KEY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::test_board;
+    use crate::models::replay::ActionKind;
+    use crate::models::storage::MemoryStorage;
+
+    fn saved(game: Game) -> SavedGame {
+        let mut replay = Replay::new(&game.board_code().expect("the game has started"));
+        replay.record(ActionKind::Flag, 0, 1500);
+        let mut clicks = ClickCounts::default();
+        clicks.add_left();
+        clicks.add_right();
+        SavedGame::new(game, 2000, Some(replay), true, clicks)
+    }
+
+    #[test]
+    fn a_game_in_progress_is_restored() {
+        let mut game = test_board(5, 1, &[0, 3], &[1]);
+        game.toggle_flag(0);
+        let saved_game = saved(game);
+        let mut storage = MemoryStorage::default();
+        saved_game.save(&mut storage);
+
+        let restored = SavedGame::load(&storage).expect("the game is still going");
+        assert_eq!(restored, saved_game);
+        assert_eq!(restored.elapsed_milliseconds(), 2000);
+        assert!(restored.is_unranked());
+        assert_eq!(restored.clicks().total(), 2);
+        assert_eq!(restored.replay().map(|replay| replay.actions().len()), Some(1));
+
+        SavedGame::clear(&mut storage);
+        assert_eq!(SavedGame::load(&storage), None);
+    }
+
+    #[test]
+    fn finished_games_are_not_restored() {
+        let mut storage = MemoryStorage::default();
+
+        saved(test_board(5, 1, &[0, 3], &[1, 3])).save(&mut storage);
+        assert_eq!(SavedGame::load(&storage), None, "lost");
+
+        saved(test_board(5, 1, &[0, 3], &[1, 2, 4])).save(&mut storage);
+        assert_eq!(SavedGame::load(&storage), None, "won");
+    }
+
+    #[test]
+    fn unstarted_games_are_not_restored() {
+        let mut storage = MemoryStorage::default();
+        SavedGame::new(Game::default(), 0, None, false, ClickCounts::default()).save(&mut storage);
+
+        assert_eq!(SavedGame::load(&storage), None);
+    }
+}
@@ -1,14 +1,47 @@
-const MAX_WIDTH: usize = 32;
-const MAX_HEIGHT: usize = 32;
-const MAX_MINES: usize = 512;
+use crate::models::neighborhood::Neighborhood;
+use crate::models::topology::Topology;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+pub const MAX_WIDTH: usize = 32;
+pub const MAX_HEIGHT: usize = 32;
+pub const MAX_DEPTH: usize = 8;
+pub const MAX_MINES: usize = 512;
+pub const MAX_MINES_PER_CELL: usize = 3;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DimensionsError {
+    InvalidWidth,
+    InvalidHeight,
+    InvalidDepth,
+    TooManyMines(usize),
+}
+
+impl fmt::Display for DimensionsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DimensionsError::InvalidWidth           => write!(f, "Width must be between 1 and {}", MAX_WIDTH),
+            DimensionsError::InvalidHeight          => write!(f, "Height must be between 1 and {}", MAX_HEIGHT),
+            DimensionsError::InvalidDepth           => write!(f, "Layers must be between 1 and {}", MAX_DEPTH),
+            DimensionsError::TooManyMines(max)      => write!(f, "There's only room for {} mines", max),
+        }
+    }
+}
+
+impl std::error::Error for DimensionsError {}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Dimensions {
     width: usize,
     height: usize,
+    /// How many layers are stacked up; anything over 1 makes the board three-dimensional.
+    #[serde(default = "one_layer")]
+    depth: usize,
     mines: usize,
 }
 
+fn one_layer() -> usize { 1 }
+
 impl Default for Dimensions {
     fn default() -> Self { Dimensions::new(32, 32, 250) }
 }
@@ -18,7 +51,11 @@ impl Dimensions {
         let w = if width  > MAX_WIDTH  { MAX_WIDTH }  else { width };
         let h = if height > MAX_HEIGHT { MAX_HEIGHT } else { height };
         let m = if mines  > MAX_MINES  { MAX_MINES }  else { mines };
-        Dimensions { width: w, height: h, mines: m }
+        Dimensions { width: w, height: h, depth: 1, mines: m }
+    }
+
+    pub fn with_depth(self, depth: usize) -> Self {
+        Dimensions { depth: depth.clamp(1, MAX_DEPTH), ..self }
     }
 
     pub fn width(&self) -> usize {
@@ -29,12 +66,28 @@ impl Dimensions {
         self.height
     }
 
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn is_layered(&self) -> bool {
+        self.depth > 1
+    }
+
     pub fn mines(&self) -> usize {
         self.mines
     }
+
+    pub fn layer_cell_count(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.width * self.height * self.depth
+    }
 }
 
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub enum Difficulty {
     #[default]
     Beginner,
@@ -44,6 +97,14 @@ pub enum Difficulty {
 }
 
 impl Difficulty {
+    /// The preset matching `dimensions`, falling back to a custom difficulty.
+    pub fn from_dimensions(dimensions: Dimensions) -> Self {
+        [Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Expert]
+            .into_iter()
+            .find(|difficulty| difficulty.dimensions() == dimensions)
+            .unwrap_or(Difficulty::Custom(dimensions))
+    }
+
     fn dimensions(&self) -> Dimensions {
         match self {
             Difficulty::Beginner => { Dimensions::new(9, 9, 10) },
@@ -63,8 +124,7 @@ impl Difficulty {
     }
 }
 
-#[allow(dead_code)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct DifficultySetting {
     difficulty: Difficulty,
     dimensions: Dimensions,
@@ -94,17 +154,40 @@ impl DifficultySetting {
     }
 }
 
-#[allow(dead_code)]
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub enum ChordSetting {
     #[default]
     LeftClick,
     LeftAndRightClick,
+    /// The middle button chords on its own, as well as both buttons together.
+    MiddleClick,
+    /// Both of the above, for players who chord with either button.
+    LeftOrMiddleClick,
     Disabled,
 }
 
-#[allow(dead_code)]
-#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+impl ChordSetting {
+    pub fn title(&self) -> String {
+        match self {
+            ChordSetting::LeftClick => { "Left click".into() },
+            ChordSetting::LeftAndRightClick => { "Left and right click".into() },
+            ChordSetting::MiddleClick => { "Middle click".into() },
+            ChordSetting::LeftOrMiddleClick => { "Left or middle click".into() },
+            ChordSetting::Disabled => { "Off".into() },
+        }
+    }
+
+    /// Whether clicking a shown number with the left button chords it.
+    pub fn chords_on_left_click(self) -> bool {
+        matches!(self, ChordSetting::LeftClick | ChordSetting::LeftOrMiddleClick)
+    }
+
+    pub fn chords_on_middle_click(self) -> bool {
+        matches!(self, ChordSetting::MiddleClick | ChordSetting::LeftOrMiddleClick)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub enum FirstClickSetting {
     Any,
     Safe,
@@ -112,26 +195,68 @@ pub enum FirstClickSetting {
     Zero,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+impl FirstClickSetting {
+    pub fn title(&self) -> String {
+        match self {
+            FirstClickSetting::Any => { "Anything".into() },
+            FirstClickSetting::Safe => { "Never a mine".into() },
+            FirstClickSetting::Zero => { "Always an opening".into() },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum GenerationSetting {
+    #[default]
+    Random,
+    /// Only boards that can be cleared by logic alone from the first click. Implies a zero first click.
+    NoGuess,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct Settings {
     difficulty_setting: DifficultySetting,
     chord_setting: ChordSetting,
     first_click_setting: FirstClickSetting,
+    generation_setting: GenerationSetting,
     allow_mark_cell_as_unknown: bool,
+    #[serde(default)]
+    topology: Topology,
+    /// Opposite edges of the board touch, like a torus.
+    #[serde(default)]
+    wrap_edges: bool,
+    /// Only used on square grids; hexagons always count the six around them.
+    #[serde(default)]
+    neighborhood: Neighborhood,
+    #[serde(default = "one_mine")]
+    max_mines_per_cell: usize,
 }
 
+fn one_mine() -> usize { 1 }
+
 impl Settings {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         difficulty_setting: DifficultySetting,
         chord_setting: ChordSetting,
         first_click_setting: FirstClickSetting,
+        generation_setting: GenerationSetting,
         allow_mark_cell_as_unknown: bool,
+        topology: Topology,
+        wrap_edges: bool,
+        neighborhood: Neighborhood,
+        max_mines_per_cell: usize,
     ) -> Self {
         Settings {
             difficulty_setting,
             chord_setting,
             first_click_setting,
+            generation_setting,
             allow_mark_cell_as_unknown,
+            topology,
+            wrap_edges,
+            neighborhood,
+            max_mines_per_cell: max_mines_per_cell.clamp(1, MAX_MINES_PER_CELL),
         }
     }
 
@@ -147,21 +272,162 @@ impl Settings {
         self.chord_setting
     }
 
+    pub fn chord_setting_is_disabled(&self) -> bool {
+        self.chord_setting == ChordSetting::Disabled
+    }
+
+    pub fn first_click_setting(&self) -> FirstClickSetting {
+        self.first_click_setting
+    }
+
     pub fn first_click_setting_is_any(&self) -> bool {
         self.first_click_setting == FirstClickSetting::Any
     }
 
     pub fn first_click_setting_is_zero(&self) -> bool {
-        self.first_click_setting == FirstClickSetting::Zero
+        self.first_click_setting == FirstClickSetting::Zero || self.generation_setting_is_no_guess()
+    }
+
+    pub fn generation_setting(&self) -> GenerationSetting {
+        self.generation_setting
+    }
+
+    /// Whether boards are laid out to need no guessing. The solver can't reason about cells holding
+    /// several mines, so no-guess is off while they're allowed.
+    pub fn generation_setting_is_no_guess(&self) -> bool {
+        self.generation_setting == GenerationSetting::NoGuess && !self.has_multi_mine_cells()
     }
 
     pub fn allow_mark_cell_as_unknown(&self) -> bool {
         self.allow_mark_cell_as_unknown
     }
 
+    pub fn topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn wrap_edges(&self) -> bool {
+        self.wrap_edges
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    pub fn neighborhood_is_disabled(&self) -> bool {
+        self.topology == Topology::Hex
+    }
+
+    /// The `(row, column)` offsets from a cell in `row` to each of its neighbors on the same layer.
+    pub fn neighbor_offsets(&self, row: usize) -> Vec<(isize, isize)> {
+        match self.topology {
+            Topology::Square => { self.neighborhood.offsets() },
+            Topology::Hex => { self.topology.neighbor_offsets(row).to_vec() },
+        }
+    }
+
+    pub fn max_mines_per_cell(&self) -> usize {
+        self.max_mines_per_cell
+    }
+
+    /// Whether a cell can hold more than one mine, which the solver doesn't reason about.
+    pub fn has_multi_mine_cells(&self) -> bool {
+        self.max_mines_per_cell > 1
+    }
+
+    pub fn wraps_columns(&self) -> bool {
+        self.wrap_edges
+    }
+
+    /// Hexagon rows alternate, so an odd number of them can't join up top to bottom and only wrap sideways.
+    pub fn wraps_rows(&self) -> bool {
+        self.wrap_edges && !(self.topology == Topology::Hex && self.dimensions().height() % 2 == 1)
+    }
+
+    /// Whether boards under `other` have the same cells and neighbors, so a game can carry on.
+    pub fn has_same_layout(&self, other: &Settings) -> bool {
+        self.dimensions() == other.dimensions() &&
+        self.topology == other.topology &&
+        self.wrap_edges == other.wrap_edges &&
+        (self.neighborhood_is_disabled() || self.neighborhood == other.neighborhood) &&
+        self.max_mines_per_cell == other.max_mines_per_cell
+    }
+
+    /// A short name for rules that change the board or how it's laid out, or `None` for the classic game.
+    pub fn variant(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if self.generation_setting_is_no_guess() { parts.push("noguess".to_string()); }
+        if self.topology == Topology::Hex { parts.push("hex".to_string()); }
+        if let Some(name) = self.neighborhood.name().filter(|_| !self.neighborhood_is_disabled()) { parts.push(name); }
+        if self.wrap_edges { parts.push("torus".to_string()); }
+        if self.has_multi_mine_cells() { parts.push(format!("multi{}", self.max_mines_per_cell)); }
+        if parts.is_empty() { None } else { Some(parts.join("-")) }
+    }
+
     pub fn set_difficulty(&mut self, difficulty: Difficulty) {
         self.difficulty_setting.set_difficulty(difficulty);
     }
+
+    /// Checks a custom board against the size limits, leaving room for the first click's safe zone.
+    pub fn validate_dimensions(&self, width: usize, height: usize, depth: usize, mines: usize) -> Result<Dimensions, DimensionsError> {
+        if width == 0 || width > MAX_WIDTH { return Err(DimensionsError::InvalidWidth); }
+        if height == 0 || height > MAX_HEIGHT { return Err(DimensionsError::InvalidHeight); }
+        if depth == 0 || depth > MAX_DEPTH { return Err(DimensionsError::InvalidDepth); }
+
+        // The first click can land anywhere, so assume the biggest safe zone a cell can have
+        let safe_cells = if self.first_click_setting_is_zero() {
+            let span = (2 * self.neighbor_reach()) + 1;
+            (self.neighbor_offsets(0).len() + 1).min(width.min(span) * height.min(span)) * depth.min(3)
+        } else if self.first_click_setting_is_any() {
+            0
+        } else {
+            1
+        };
+        let max_mines = ((width * height * depth - safe_cells) * self.max_mines_per_cell).min(MAX_MINES);
+        if mines > max_mines { return Err(DimensionsError::TooManyMines(max_mines)); }
+
+        Ok(Dimensions::new(width, height, mines).with_depth(depth))
+    }
+
+    pub fn set_chord_setting(&mut self, chord_setting: ChordSetting) {
+        self.chord_setting = chord_setting;
+    }
+
+    pub fn set_first_click_setting(&mut self, first_click_setting: FirstClickSetting) {
+        self.first_click_setting = first_click_setting;
+    }
+
+    pub fn set_generation_setting(&mut self, generation_setting: GenerationSetting) {
+        self.generation_setting = generation_setting;
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    pub fn set_neighborhood(&mut self, neighborhood: Neighborhood) {
+        self.neighborhood = neighborhood;
+    }
+
+    pub fn set_max_mines_per_cell(&mut self, max_mines_per_cell: usize) {
+        self.max_mines_per_cell = max_mines_per_cell.clamp(1, MAX_MINES_PER_CELL);
+    }
+
+    pub fn set_wrap_edges(&mut self, wrap_edges: bool) {
+        self.wrap_edges = wrap_edges;
+    }
+
+    pub fn set_allow_mark_cell_as_unknown(&mut self, allow_mark_cell_as_unknown: bool) {
+        self.allow_mark_cell_as_unknown = allow_mark_cell_as_unknown;
+    }
+
+    // Private methods
+    fn neighbor_reach(&self) -> usize {
+        match self.topology {
+            Topology::Square => { self.neighborhood.reach() },
+            Topology::Hex => { 1 },
+        }
+    }
 }
 
 impl Default for Settings {
@@ -170,7 +436,128 @@ impl Default for Settings {
             DifficultySetting::default(),
             ChordSetting::default(),
             FirstClickSetting::Zero,
-            false
+            GenerationSetting::default(),
+            false,
+            Topology::default(),
+            false,
+            Neighborhood::default(),
+            1,
         )
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_first_click(first_click_setting: FirstClickSetting) -> Settings {
+        let mut settings = Settings::default();
+        settings.set_first_click_setting(first_click_setting);
+        settings
+    }
+
+    #[test]
+    fn custom_boards_must_fit_the_size_limits() {
+        let settings = Settings::default();
+
+        assert_eq!(settings.validate_dimensions(0, 9, 1, 10), Err(DimensionsError::InvalidWidth));
+        assert_eq!(settings.validate_dimensions(MAX_WIDTH + 1, 9, 1, 10), Err(DimensionsError::InvalidWidth));
+        assert_eq!(settings.validate_dimensions(9, 0, 1, 10), Err(DimensionsError::InvalidHeight));
+        assert_eq!(settings.validate_dimensions(9, MAX_HEIGHT + 1, 1, 10), Err(DimensionsError::InvalidHeight));
+        assert_eq!(settings.validate_dimensions(MAX_WIDTH, MAX_HEIGHT, 1, MAX_MINES + 1), Err(DimensionsError::TooManyMines(MAX_MINES)));
+
+        let dimensions = settings.validate_dimensions(MAX_WIDTH, MAX_HEIGHT, 1, MAX_MINES).unwrap();
+        assert_eq!((dimensions.width(), dimensions.height(), dimensions.mines()), (MAX_WIDTH, MAX_HEIGHT, MAX_MINES));
+    }
+
+    #[test]
+    fn custom_boards_leave_room_for_the_first_click() {
+        let any = with_first_click(FirstClickSetting::Any);
+        let safe = with_first_click(FirstClickSetting::Safe);
+        let zero = with_first_click(FirstClickSetting::Zero);
+
+        assert!(any.validate_dimensions(9, 9, 1, 81).is_ok());
+        assert_eq!(safe.validate_dimensions(9, 9, 1, 81), Err(DimensionsError::TooManyMines(80)));
+        assert!(safe.validate_dimensions(9, 9, 1, 80).is_ok());
+        assert_eq!(zero.validate_dimensions(9, 9, 1, 73), Err(DimensionsError::TooManyMines(72)));
+        assert!(zero.validate_dimensions(9, 9, 1, 72).is_ok());
+        // A board too small for a whole 3x3 opening only needs to keep its own cells clear
+        assert_eq!(zero.validate_dimensions(2, 1, 1, 1), Err(DimensionsError::TooManyMines(0)));
+    }
+
+    #[test]
+    fn custom_dimensions_are_kept_by_the_difficulty() {
+        let dimensions = Dimensions::new(12, 7, 20);
+        let mut settings = Settings::default();
+        settings.set_difficulty(Difficulty::Custom(dimensions));
+
+        assert_eq!(settings.difficulty(), Difficulty::Custom(dimensions));
+        assert_eq!(settings.dimensions(), dimensions);
+        assert_eq!(Difficulty::from_dimensions(Dimensions::new(16, 16, 40)), Difficulty::Intermediate);
+    }
+
+    #[test]
+    fn settings_survive_a_save_and_load() {
+        let mut settings = Settings::default();
+        settings.set_chord_setting(ChordSetting::Disabled);
+        settings.set_first_click_setting(FirstClickSetting::Safe);
+        settings.set_allow_mark_cell_as_unknown(true);
+
+        let json = serde_json::to_string(&settings).unwrap();
+        let loaded: Settings = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, settings);
+        assert!(loaded.chord_setting_is_disabled());
+        assert!(loaded.allow_mark_cell_as_unknown());
+    }
+
+    #[test]
+    fn settings_saved_before_newer_options_load_with_their_defaults() {
+        let mut json = serde_json::to_value(Settings::default()).unwrap();
+        let fields = json.as_object_mut().unwrap();
+        for field in ["topology", "wrap_edges", "neighborhood", "max_mines_per_cell"] {
+            fields.remove(field);
+        }
+        fields["difficulty_setting"]["dimensions"].as_object_mut().unwrap().remove("depth");
+
+        let loaded: Settings = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded, Settings::default());
+    }
+
+    #[test]
+    fn layered_boards_stay_within_the_depth_limit() {
+        let settings = Settings::default();
+
+        assert_eq!(settings.validate_dimensions(9, 9, 0, 10), Err(DimensionsError::InvalidDepth));
+        assert_eq!(settings.validate_dimensions(9, 9, MAX_DEPTH + 1, 10), Err(DimensionsError::InvalidDepth));
+        assert_eq!(settings.validate_dimensions(9, 9, MAX_DEPTH, 10).map(|dimensions| dimensions.depth()), Ok(MAX_DEPTH));
+        assert_eq!(Dimensions::new(9, 9, 10).with_depth(MAX_DEPTH + 1).depth(), MAX_DEPTH);
+        assert_eq!(Dimensions::new(9, 9, 10).with_depth(0).depth(), 1);
+    }
+
+    #[test]
+    fn the_first_click_safe_zone_reaches_the_layers_either_side() {
+        let zero = with_first_click(FirstClickSetting::Zero);
+        let safe = with_first_click(FirstClickSetting::Safe);
+
+        // A 3x3 square on one layer, then on two, then on three
+        assert_eq!(zero.validate_dimensions(9, 9, 1, MAX_MINES), Err(DimensionsError::TooManyMines(81 - 9)));
+        assert_eq!(zero.validate_dimensions(9, 9, 2, MAX_MINES), Err(DimensionsError::TooManyMines(162 - 18)));
+        assert_eq!(zero.validate_dimensions(9, 9, 3, MAX_MINES), Err(DimensionsError::TooManyMines(243 - 27)));
+        assert_eq!(zero.validate_dimensions(9, 9, 5, MAX_MINES), Err(DimensionsError::TooManyMines(405 - 27)));
+        assert_eq!(safe.validate_dimensions(9, 9, 3, MAX_MINES), Err(DimensionsError::TooManyMines(243 - 1)));
+    }
+
+    #[test]
+    fn no_guess_is_off_while_cells_can_hold_several_mines() {
+        let mut settings = with_first_click(FirstClickSetting::Safe);
+        settings.set_generation_setting(GenerationSetting::NoGuess);
+        assert!(settings.generation_setting_is_no_guess());
+        assert!(settings.first_click_setting_is_zero());
+        assert_eq!(settings.variant().as_deref(), Some("noguess"));
+
+        settings.set_max_mines_per_cell(2);
+        assert!(!settings.generation_setting_is_no_guess());
+        assert!(!settings.first_click_setting_is_zero());
+        assert_eq!(settings.variant().as_deref(), Some("multi2"));
+        assert_eq!(settings.generation_setting(), GenerationSetting::NoGuess);
+    }
+}
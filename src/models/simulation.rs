@@ -0,0 +1,266 @@
+use crate::models::game::Game;
+use crate::models::metrics::{openings, three_bv};
+use crate::models::settings::Settings;
+use crate::models::solver::Solver;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+
+// Keeps a random first click from lining up with the mines drawn from the same seed
+const FIRST_CLICK_SALT: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Where the simulated player makes their first click.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FirstClick {
+    #[default]
+    Random,
+    /// The middle cell of the middle layer.
+    Center,
+    Corner,
+    Index(usize),
+}
+
+impl FirstClick {
+    /// The cell clicked on a board of `settings` generated from `seed`.
+    pub fn index(&self, settings: &Settings, seed: u64) -> usize {
+        let dimensions = settings.dimensions();
+        match self {
+            FirstClick::Random => { ChaCha8Rng::seed_from_u64(seed ^ FIRST_CLICK_SALT).gen_range(0..dimensions.cell_count()) },
+            FirstClick::Center => { (dimensions.depth() / 2 * dimensions.layer_cell_count()) + (dimensions.height() / 2 * dimensions.width()) + (dimensions.width() / 2) },
+            FirstClick::Corner => { 0 },
+            FirstClick::Index(index) => { (*index).min(dimensions.cell_count() - 1) },
+        }
+    }
+}
+
+/// What one generated board looked like straight after its first click.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoardSample {
+    three_bv: usize,
+    openings: usize,
+    /// `None` when the solver can't judge the board, as with several mines to a cell.
+    solvable: Option<bool>,
+    first_click_is_mine: bool,
+    first_click_neighbors: usize,
+    /// Counting each mine in a cell.
+    first_click_neighboring_mines: usize,
+}
+
+impl BoardSample {
+    /// Lays out a board on `game` from `seed`, opens it at `first_click` and measures it.
+    /// Judging whether it's solvable takes far longer than the rest, so it can be skipped.
+    pub fn generate(game: &mut Game, seed: u64, first_click: FirstClick, judge_solvable: bool) -> Self {
+        let settings = game.settings();
+        let index = first_click.index(&settings, seed);
+        game.reset_with_seed(seed);
+        game.reveal(index);
+
+        let neighbors = game.neighbors(index);
+        let first_click_neighboring_mines = neighbors.iter().map(|neighbor| game.cell(*neighbor).mine_count()).sum();
+        let solvable = (judge_solvable && !settings.has_multi_mine_cells()).then(|| Solver::is_solvable(game.neighbor_sets(), game.cells(), index));
+
+        BoardSample {
+            three_bv: three_bv(game).0,
+            openings: openings(game),
+            solvable,
+            first_click_is_mine: game.cell(index).is_mine(),
+            first_click_neighbors: neighbors.len(),
+            first_click_neighboring_mines,
+        }
+    }
+
+    pub fn three_bv(&self) -> usize {
+        self.three_bv
+    }
+
+    pub fn openings(&self) -> usize {
+        self.openings
+    }
+
+    pub fn solvable(&self) -> Option<bool> {
+        self.solvable
+    }
+
+    pub fn first_click_is_mine(&self) -> bool {
+        self.first_click_is_mine
+    }
+
+    /// The share of the first click's neighbors that hold mines.
+    pub fn first_click_density(&self) -> f64 {
+        if self.first_click_neighbors == 0 { return 0.0; }
+        self.first_click_neighboring_mines as f64 / self.first_click_neighbors as f64
+    }
+}
+
+/// How often each whole-number value came up.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Distribution {
+    counts: BTreeMap<usize, u64>,
+}
+
+impl Distribution {
+    pub fn record(&mut self, value: usize) {
+        *self.counts.entry(value).or_default() += 1;
+    }
+
+    pub fn merge(&mut self, other: &Distribution) {
+        for (value, count) in &other.counts {
+            *self.counts.entry(*value).or_default() += count;
+        }
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+
+    /// Each value alongside how many times it came up, smallest first.
+    pub fn counts(&self) -> &BTreeMap<usize, u64> {
+        &self.counts
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.counts.keys().next().copied()
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        self.counts.keys().next_back().copied()
+    }
+
+    pub fn mean(&self) -> f64 {
+        let total = self.total();
+        if total == 0 { return 0.0; }
+        self.counts.iter().map(|(value, count)| *value as f64 * *count as f64).sum::<f64>() / total as f64
+    }
+
+    /// The smallest value at least `fraction` of the samples are no bigger than.
+    pub fn percentile(&self, fraction: f64) -> Option<usize> {
+        let target = (fraction.clamp(0.0, 1.0) * self.total() as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        self.counts.iter().find_map(|(value, count)| {
+            seen += count;
+            (seen >= target).then_some(*value)
+        })
+    }
+}
+
+/// Everything measured over a batch of boards; batches from separate threads can be merged.
+///
+/// Only whole numbers are added up, so merging gives the same report in any order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimulationReport {
+    boards: u64,
+    three_bv: Distribution,
+    openings: Distribution,
+    solvable: u64,
+    judged: u64,
+    first_click_mines: u64,
+    first_click_neighbors: u64,
+    first_click_neighboring_mines: u64,
+}
+
+impl SimulationReport {
+    /// Generates `boards` boards of `settings` from consecutive seeds starting at `first_seed`.
+    pub fn run(settings: Settings, first_click: FirstClick, judge_solvable: bool, first_seed: u64, boards: u64) -> Self {
+        // Left without a clock, so no-guess boards give up after a fixed number of tries and come out the same on any machine
+        let mut game = Game::new(settings);
+        let mut report = SimulationReport::default();
+        for offset in 0..boards {
+            report.record(&BoardSample::generate(&mut game, first_seed.wrapping_add(offset), first_click, judge_solvable));
+        }
+        report
+    }
+
+    pub fn record(&mut self, sample: &BoardSample) {
+        self.boards += 1;
+        self.three_bv.record(sample.three_bv);
+        self.openings.record(sample.openings);
+        if let Some(solvable) = sample.solvable {
+            self.judged += 1;
+            if solvable { self.solvable += 1; }
+        }
+        if sample.first_click_is_mine { self.first_click_mines += 1; }
+        self.first_click_neighbors += sample.first_click_neighbors as u64;
+        self.first_click_neighboring_mines += sample.first_click_neighboring_mines as u64;
+    }
+
+    pub fn merge(&mut self, other: &SimulationReport) {
+        self.boards += other.boards;
+        self.three_bv.merge(&other.three_bv);
+        self.openings.merge(&other.openings);
+        self.solvable += other.solvable;
+        self.judged += other.judged;
+        self.first_click_mines += other.first_click_mines;
+        self.first_click_neighbors += other.first_click_neighbors;
+        self.first_click_neighboring_mines += other.first_click_neighboring_mines;
+    }
+
+    pub fn boards(&self) -> u64 {
+        self.boards
+    }
+
+    pub fn three_bv(&self) -> &Distribution {
+        &self.three_bv
+    }
+
+    pub fn openings(&self) -> &Distribution {
+        &self.openings
+    }
+
+    /// The share of boards that can be cleared by logic alone from the first click, or `None` if none could be judged.
+    pub fn solvable_fraction(&self) -> Option<f64> {
+        if self.judged == 0 { return None; }
+        Some(self.solvable as f64 / self.judged as f64)
+    }
+
+    pub fn first_click_mine_fraction(&self) -> f64 {
+        if self.boards == 0 { return 0.0; }
+        self.first_click_mines as f64 / self.boards as f64
+    }
+
+    /// The share of all the first clicks' neighbors that hold mines, taken together.
+    pub fn first_click_density(&self) -> f64 {
+        if self.first_click_neighbors == 0 { return 0.0; }
+        self.first_click_neighboring_mines as f64 / self.first_click_neighbors as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::settings::{Difficulty, Dimensions};
+
+    fn settings_for(dimensions: Dimensions) -> Settings {
+        let mut settings = Settings::default();
+        settings.set_difficulty(Difficulty::Custom(dimensions));
+        settings
+    }
+
+    #[test]
+    fn center_clicks_the_middle_of_the_middle_layer() {
+        assert_eq!(FirstClick::Center.index(&settings_for(Dimensions::new(9, 9, 10)), 0), 40);
+        assert_eq!(FirstClick::Center.index(&settings_for(Dimensions::new(9, 9, 10).with_depth(3)), 0), 81 + 40);
+        assert_eq!(FirstClick::Center.index(&settings_for(Dimensions::new(4, 2, 1).with_depth(4)), 0), (2 * 8) + 4 + 2);
+    }
+
+    #[test]
+    fn first_clicks_stay_on_the_board() {
+        let settings = settings_for(Dimensions::new(9, 9, 10).with_depth(2));
+
+        assert_eq!(FirstClick::Corner.index(&settings, 0), 0);
+        assert_eq!(FirstClick::Index(500).index(&settings, 0), 161);
+        assert!((0..50).all(|seed| FirstClick::Random.index(&settings, seed) < 162));
+        assert_eq!(FirstClick::Random.index(&settings, 3), FirstClick::Random.index(&settings, 3));
+    }
+
+    #[test]
+    fn split_runs_merge_into_the_same_report() {
+        let settings = Settings::default();
+        let whole = SimulationReport::run(settings, FirstClick::Random, true, 7, 30);
+
+        let mut merged = SimulationReport::run(settings, FirstClick::Random, true, 7 + 11, 19);
+        merged.merge(&SimulationReport::run(settings, FirstClick::Random, true, 7, 11));
+
+        assert_eq!(merged, whole);
+        assert_eq!(merged.boards(), 30);
+    }
+}
@@ -0,0 +1,551 @@
+use crate::models::cell::Cell;
+use crate::models::game::Game;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// Enumeration is exponential in the worst case, so give up on constraint groups that are too big
+const MAX_ENUMERATION_CELLS: usize = 48;
+const MAX_ENUMERATION_STEPS: usize = 200_000;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Deduction {
+    Safe(usize),
+    Mine(usize),
+}
+
+impl Deduction {
+    pub fn index(&self) -> usize {
+        match self {
+            Deduction::Safe(index) | Deduction::Mine(index) => *index,
+        }
+    }
+
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Deduction::Safe(_))
+    }
+}
+
+/// The rule that produced a deduction, from cheapest to most expensive.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Reason {
+    /// One number on its own: either all of its mines are found or all of its hidden neighbors are mines.
+    SingleCell,
+    /// One number's hidden neighbors all touch a second number, which pins down the rest of the second's.
+    Subset,
+    /// Every mine arrangement that satisfies a connected group of numbers agrees on the cell.
+    Enumeration,
+}
+
+/// A deduction along with the shown numbers that force it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Hint {
+    deduction: Deduction,
+    reason: Reason,
+    sources: Vec<usize>,
+}
+
+impl Hint {
+    pub fn deduction(&self) -> Deduction {
+        self.deduction
+    }
+
+    pub fn reason(&self) -> Reason {
+        self.reason
+    }
+
+    /// The shown numbers this hint was deduced from.
+    pub fn sources(&self) -> &[usize] {
+        &self.sources
+    }
+
+    pub fn explanation(&self, game: &Game) -> String {
+        let target = describe_index(game, self.deduction.index());
+        let outcome = if self.deduction.is_safe() { "is safe" } else { "is a mine" };
+        let numbers: Vec<String> = self.sources.iter().map(|index| describe_number(game, *index)).collect();
+
+        match (self.reason, numbers.as_slice()) {
+            (Reason::SingleCell, [number, ..]) if self.deduction.is_safe() => {
+                format!("The {} already touches all of its mines, so {} {}.", number, target, outcome)
+            },
+            (Reason::SingleCell, [number, ..]) => {
+                format!("The {} needs every one of its hidden neighbors to be a mine, so {} {}.", number, target, outcome)
+            },
+            (Reason::Subset, [smaller, larger, ..]) => {
+                format!(
+                    "Every hidden neighbor of the {} also touches the {}, so the {}'s other neighbors hold exactly its remaining mines: {} {}.",
+                    smaller, larger, larger, target, outcome,
+                )
+            },
+            _ if numbers.len() > 3 => {
+                format!("Every mine arrangement that fits the {} highlighted numbers agrees that {} {}.", numbers.len(), target, outcome)
+            },
+            _ => {
+                format!("Every mine arrangement that fits the {} agrees that {} {}.", numbers.join(", "), target, outcome)
+            },
+        }
+    }
+}
+
+// The hidden cells around one shown number, and how many of them are still unaccounted-for mines
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct Constraint {
+    cells: HashSet<usize>,
+    mines: usize,
+    source: usize,
+}
+
+/// Every mine arrangement of one connected group of hidden cells, tallied by how many mines it uses.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Enumeration {
+    cells: Vec<usize>,
+    sources: Vec<usize>,
+    // mines used -> (arrangements, arrangements with a mine in each of `cells`)
+    by_mine_count: BTreeMap<usize, (u64, Vec<u64>)>,
+}
+
+impl Enumeration {
+    pub fn cells(&self) -> &[usize] {
+        &self.cells
+    }
+
+    /// For each possible number of mines in the group: the number of arrangements using that many
+    /// mines, and how many of those put a mine on each of [`Enumeration::cells`].
+    pub fn by_mine_count(&self) -> &BTreeMap<usize, (u64, Vec<u64>)> {
+        &self.by_mine_count
+    }
+}
+
+/// Works out which hidden cells are provably safe or provably mines from the shown numbers alone.
+///
+/// Flags are ignored, since the player may have placed them wrongly.
+pub struct Solver<'a> {
+    neighbors:  &'a [HashSet<usize>],
+    revealed:   Vec<Option<usize>>,
+    mines:      HashSet<usize>,
+    safe:       HashSet<usize>,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(game: &'a Game) -> Self {
+        let revealed = game.cells().iter().map(|cell| cell.shown_number()).collect();
+        Solver::from_revealed(game.neighbor_sets(), revealed)
+    }
+
+    /// Whether a player who opens `index_clicked` can clear the board in `cells` without ever guessing.
+    pub fn is_solvable(neighbors: &[HashSet<usize>], cells: &[Cell], index_clicked: usize) -> bool {
+        if cells[index_clicked].is_mine() { return false; }
+
+        let mut solver = Solver::from_revealed(neighbors, vec![None; cells.len()]);
+        solver.open(cells, index_clicked);
+        loop {
+            solver.deduce();
+            let safe: Vec<usize> = solver.safe.iter().copied().collect();
+            if safe.is_empty() { break; }
+            for index in safe {
+                solver.open(cells, index);
+            }
+        }
+
+        let mine_count = cells.iter().filter(|cell| cell.is_mine()).count();
+        solver.revealed.iter().filter(|number| number.is_some()).count() + mine_count == cells.len()
+    }
+
+    /// Every deduction that follows from the shown numbers, including ones that need earlier deductions.
+    pub fn deduce(&mut self) -> Vec<Deduction> {
+        let mut deductions = Vec::new();
+        loop {
+            let hints = self.deduce_step();
+            if hints.is_empty() { break; }
+
+            for hint in hints {
+                self.learn(hint.deduction);
+                deductions.push(hint.deduction);
+            }
+        }
+        deductions
+    }
+
+    /// The simplest deduction the player hasn't already acted on, skipping mines they've flagged.
+    pub fn hint(&mut self, game: &Game) -> Option<Hint> {
+        loop {
+            let hints = self.deduce_step();
+            if hints.is_empty() { return None; }
+
+            let useful = hints.iter().find(|hint| hint.deduction.is_safe() || !game.cell(hint.deduction.index()).is_flagged());
+            if let Some(hint) = useful { return Some(hint.clone()); }
+
+            for hint in hints {
+                self.learn(hint.deduction);
+            }
+        }
+    }
+
+    /// All mine arrangements of each connected group of hidden cells next to a shown number.
+    /// Groups too large to enumerate are left out.
+    pub fn enumerations(&self) -> Vec<Enumeration> {
+        let constraints = self.constraints();
+        Solver::groups(&constraints)
+            .into_iter()
+            .filter_map(|group| Solver::enumerate(&group))
+            .collect()
+    }
+
+    /// Hidden cells that haven't been deduced to be safe or mines.
+    pub fn unknown_cells(&self) -> Vec<usize> {
+        (0..self.revealed.len()).filter(|index| self.is_unknown(*index)).collect()
+    }
+
+    pub fn known_mines(&self) -> &HashSet<usize> {
+        &self.mines
+    }
+
+    pub fn known_safe(&self) -> &HashSet<usize> {
+        &self.safe
+    }
+
+    // Private methods
+    fn from_revealed(neighbors: &'a [HashSet<usize>], revealed: Vec<Option<usize>>) -> Self {
+        Solver { neighbors, revealed, mines: HashSet::new(), safe: HashSet::new() }
+    }
+
+    fn is_unknown(&self, index: usize) -> bool {
+        self.revealed[index].is_none() && !self.mines.contains(&index) && !self.safe.contains(&index)
+    }
+
+    fn learn(&mut self, deduction: Deduction) {
+        match deduction {
+            Deduction::Safe(index) => { self.safe.insert(index); },
+            Deduction::Mine(index) => { self.mines.insert(index); },
+        }
+    }
+
+    fn open(&mut self, cells: &[Cell], index: usize) {
+        let mut to_open = vec![index];
+        while let Some(index) = to_open.pop() {
+            if self.revealed[index].is_some() { continue; }
+
+            let number = cells[index].value().number();
+            self.revealed[index] = number;
+            self.safe.remove(&index);
+            if number == Some(0) { to_open.extend(self.neighbors[index].iter().copied()); }
+        }
+    }
+
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints: Vec<Constraint> = Vec::new();
+        for (index, number) in self.revealed.iter().enumerate() {
+            let Some(number) = number else { continue; };
+
+            let cells: HashSet<usize> = self.neighbors[index].iter().copied().filter(|n| self.is_unknown(*n)).collect();
+            if cells.is_empty() { continue; }
+            let known_mines = self.neighbors[index].iter().filter(|n| self.mines.contains(n)).count();
+            let mines = number.saturating_sub(known_mines);
+            if constraints.iter().any(|constraint| constraint.cells == cells && constraint.mines == mines) { continue; }
+            constraints.push(Constraint { cells, mines, source: index });
+        }
+        constraints
+    }
+
+    // Runs the cheapest rule that finds anything
+    fn deduce_step(&self) -> Vec<Hint> {
+        let constraints = self.constraints();
+
+        let mut hints = Vec::new();
+        for constraint in &constraints {
+            hints.extend(Solver::resolve(&constraint.cells, constraint.mines, Reason::SingleCell, vec![constraint.source]));
+        }
+        if !hints.is_empty() { return Solver::dedup(hints); }
+
+        let mut constraints_by_cell: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, constraint) in constraints.iter().enumerate() {
+            for cell in &constraint.cells {
+                constraints_by_cell.entry(*cell).or_default().push(i);
+            }
+        }
+        for smaller in &constraints {
+            let Some(first_cell) = smaller.cells.iter().next() else { continue; };
+            for j in &constraints_by_cell[first_cell] {
+                let larger = &constraints[*j];
+                if larger.cells.len() <= smaller.cells.len() || !smaller.cells.is_subset(&larger.cells) { continue; }
+                if larger.mines < smaller.mines { continue; }
+
+                let difference: HashSet<usize> = larger.cells.difference(&smaller.cells).copied().collect();
+                hints.extend(Solver::resolve(&difference, larger.mines - smaller.mines, Reason::Subset, vec![smaller.source, larger.source]));
+            }
+        }
+        if !hints.is_empty() { return Solver::dedup(hints); }
+
+        for group in Solver::groups(&constraints) {
+            let Some(enumeration) = Solver::enumerate(&group) else { continue; };
+
+            let total: u64 = enumeration.by_mine_count.values().map(|(count, _)| count).sum();
+            if total == 0 { continue; }
+            for (i, cell) in enumeration.cells.iter().enumerate() {
+                let with_mine: u64 = enumeration.by_mine_count.values().map(|(_, mines)| mines[i]).sum();
+                let deduction = if with_mine == 0 {
+                    Deduction::Safe(*cell)
+                } else if with_mine == total {
+                    Deduction::Mine(*cell)
+                } else {
+                    continue;
+                };
+                hints.push(Hint { deduction, reason: Reason::Enumeration, sources: enumeration.sources.clone() });
+            }
+        }
+        Solver::dedup(hints)
+    }
+
+    fn resolve(cells: &HashSet<usize>, mines: usize, reason: Reason, sources: Vec<usize>) -> Vec<Hint> {
+        let deduction: fn(usize) -> Deduction = if mines == 0 {
+            Deduction::Safe
+        } else if mines == cells.len() {
+            Deduction::Mine
+        } else {
+            return Vec::new();
+        };
+
+        let mut cells: Vec<usize> = cells.iter().copied().collect();
+        cells.sort_unstable();
+        cells.into_iter().map(|index| Hint { deduction: deduction(index), reason, sources: sources.clone() }).collect()
+    }
+
+    fn dedup(hints: Vec<Hint>) -> Vec<Hint> {
+        let mut seen = HashSet::new();
+        hints.into_iter().filter(|hint| seen.insert(hint.deduction)).collect()
+    }
+
+    // Splits constraints into groups that share no hidden cells with each other
+    fn groups(constraints: &[Constraint]) -> Vec<Vec<&Constraint>> {
+        let mut group_of_cell: HashMap<usize, usize> = HashMap::new();
+        let mut groups: Vec<Vec<&Constraint>> = Vec::new();
+        for constraint in constraints {
+            let mut touching: Vec<usize> = constraint.cells.iter().filter_map(|cell| group_of_cell.get(cell).copied()).collect();
+            touching.sort_unstable();
+            touching.dedup();
+
+            let group = match touching.first() {
+                Some(first) => *first,
+                None => {
+                    groups.push(Vec::new());
+                    groups.len() - 1
+                },
+            };
+            for other in touching.iter().skip(1).rev() {
+                let merged = std::mem::take(&mut groups[*other]);
+                for member in &merged {
+                    for cell in &member.cells {
+                        group_of_cell.insert(*cell, group);
+                    }
+                }
+                groups[group].extend(merged);
+            }
+            for cell in &constraint.cells {
+                group_of_cell.insert(*cell, group);
+            }
+            groups[group].push(constraint);
+        }
+        groups.into_iter().filter(|group| !group.is_empty()).collect()
+    }
+
+    fn enumerate(group: &[&Constraint]) -> Option<Enumeration> {
+        let mut cells: Vec<usize> = Vec::new();
+        for constraint in group {
+            let mut new_cells: Vec<usize> = constraint.cells.iter().copied().filter(|cell| !cells.contains(cell)).collect();
+            new_cells.sort_unstable();
+            cells.extend(new_cells);
+        }
+        if cells.len() > MAX_ENUMERATION_CELLS { return None; }
+
+        let position: HashMap<usize, usize> = cells.iter().enumerate().map(|(i, cell)| (*cell, i)).collect();
+        let mut search = EnumerationSearch {
+            constraint_cells: group.iter().map(|constraint| constraint.cells.iter().map(|cell| position[cell]).collect()).collect(),
+            targets: group.iter().map(|constraint| constraint.mines).collect(),
+            constraints_of_cell: vec![Vec::new(); cells.len()],
+            assigned_mines: vec![0; group.len()],
+            unassigned: group.iter().map(|constraint| constraint.cells.len()).collect(),
+            assignment: vec![false; cells.len()],
+            steps: 0,
+            by_mine_count: BTreeMap::new(),
+        };
+        for (c, constraint_cells) in search.constraint_cells.iter().enumerate() {
+            for cell in constraint_cells {
+                search.constraints_of_cell[*cell].push(c);
+            }
+        }
+
+        if !search.run(0, 0) { return None; }
+
+        let mut sources: Vec<usize> = group.iter().map(|constraint| constraint.source).collect();
+        sources.sort_unstable();
+        Some(Enumeration { cells, sources, by_mine_count: search.by_mine_count })
+    }
+}
+
+// Backtracking over one group's cells, pruning as soon as a constraint can no longer be met
+struct EnumerationSearch {
+    constraint_cells: Vec<Vec<usize>>,
+    targets: Vec<usize>,
+    constraints_of_cell: Vec<Vec<usize>>,
+    assigned_mines: Vec<usize>,
+    unassigned: Vec<usize>,
+    assignment: Vec<bool>,
+    steps: usize,
+    by_mine_count: BTreeMap<usize, (u64, Vec<u64>)>,
+}
+
+impl EnumerationSearch {
+    // Returns false if the step budget ran out
+    fn run(&mut self, cell: usize, mines: usize) -> bool {
+        self.steps += 1;
+        if self.steps > MAX_ENUMERATION_STEPS { return false; }
+
+        if cell == self.assignment.len() {
+            let cell_count = self.assignment.len();
+            let (count, cell_mines) = self.by_mine_count.entry(mines).or_insert_with(|| (0, vec![0; cell_count]));
+            *count += 1;
+            for (i, is_mine) in self.assignment.iter().enumerate() {
+                if *is_mine { cell_mines[i] += 1; }
+            }
+            return true;
+        }
+
+        for is_mine in [false, true] {
+            self.assignment[cell] = is_mine;
+            for c in &self.constraints_of_cell[cell] {
+                self.unassigned[*c] -= 1;
+                if is_mine { self.assigned_mines[*c] += 1; }
+            }
+
+            let feasible = self.constraints_of_cell[cell].iter().all(|c| {
+                self.assigned_mines[*c] <= self.targets[*c] && self.assigned_mines[*c] + self.unassigned[*c] >= self.targets[*c]
+            });
+            let completed = !feasible || self.run(cell + 1, mines + usize::from(is_mine));
+
+            for c in &self.constraints_of_cell[cell] {
+                self.unassigned[*c] += 1;
+                if is_mine { self.assigned_mines[*c] -= 1; }
+            }
+            if !completed { return false; }
+        }
+        self.assignment[cell] = false;
+        true
+    }
+}
+
+fn describe_index(game: &Game, index: usize) -> String {
+    let (row, col) = game.get_row_col_from_index(index);
+    if game.settings().dimensions().is_layered() {
+        return format!("layer {}, row {}, column {}", game.get_layer_from_index(index) + 1, row + 1, col + 1);
+    }
+    format!("row {}, column {}", row + 1, col + 1)
+}
+
+fn describe_number(game: &Game, index: usize) -> String {
+    let number = game.cell(index).shown_number().unwrap_or_default();
+    format!("{} at {}", number, describe_index(game, index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::game::test_board;
+
+    // Shows exactly `shown`, without the flood fill a real click would set off
+    fn solver_showing<'a>(game: &'a Game, shown: &[usize]) -> Solver<'a> {
+        let revealed = (0..game.cells().len())
+            .map(|index| if shown.contains(&index) { game.cell(index).value().number() } else { None })
+            .collect();
+        Solver::from_revealed(game.neighbor_sets(), revealed)
+    }
+
+    fn deductions(hints: &[Hint]) -> Vec<Deduction> {
+        let mut deductions: Vec<Deduction> = hints.iter().map(|hint| hint.deduction).collect();
+        deductions.sort_unstable_by_key(|deduction| deduction.index());
+        deductions
+    }
+
+    #[test]
+    fn single_cell_finds_a_number_full_of_mines() {
+        // 1 *
+        let game = test_board(2, 1, &[1], &[]);
+        let hints = solver_showing(&game, &[0]).deduce_step();
+
+        assert_eq!(deductions(&hints), vec![Deduction::Mine(1)]);
+        assert!(hints.iter().all(|hint| hint.reason == Reason::SingleCell && hint.sources == vec![0]));
+    }
+
+    #[test]
+    fn single_cell_clears_around_a_satisfied_number() {
+        // * 1 0 0, with only the first zero shown
+        let game = test_board(4, 1, &[0], &[]);
+        let hints = solver_showing(&game, &[2]).deduce_step();
+
+        assert_eq!(deductions(&hints), vec![Deduction::Safe(1), Deduction::Safe(3)]);
+        assert!(hints.iter().all(|hint| hint.reason == Reason::SingleCell));
+    }
+
+    #[test]
+    fn subset_compares_overlapping_numbers() {
+        // * ? *
+        // 1 2 1
+        let game = test_board(3, 2, &[0, 2], &[]);
+        let hints = solver_showing(&game, &[3, 4, 5]).deduce_step();
+
+        assert_eq!(deductions(&hints), vec![Deduction::Mine(0), Deduction::Mine(2)]);
+        assert!(hints.iter().all(|hint| hint.reason == Reason::Subset));
+        assert!(hints.iter().all(|hint| hint.sources.contains(&4)));
+    }
+
+    #[test]
+    fn enumeration_finds_what_every_arrangement_agrees_on() {
+        // 2 ? ?
+        // * * 1
+        // Neither number's cells hold the other's, but only {1, 3} and {3, 4} fit both
+        let game = test_board(3, 2, &[3, 4], &[]);
+        let hints = solver_showing(&game, &[0, 5]).deduce_step();
+
+        assert_eq!(deductions(&hints), vec![Deduction::Safe(2), Deduction::Mine(3)]);
+        assert!(hints.iter().all(|hint| hint.reason == Reason::Enumeration && hint.sources == vec![0, 5]));
+    }
+
+    #[test]
+    fn deduce_follows_earlier_deductions() {
+        // 1 * 1 0: the first 1 finds the mine, which leaves the second 1 with nothing else to hide
+        let game = test_board(4, 1, &[1], &[]);
+        assert_eq!(solver_showing(&game, &[0]).deduce(), vec![Deduction::Mine(1)]);
+        assert!(solver_showing(&game, &[0, 2]).deduce().contains(&Deduction::Safe(3)));
+    }
+
+    #[test]
+    fn hint_skips_mines_the_player_has_flagged() {
+        // * 1 0 1 * 1, opened from the middle so the last cell stays hidden
+        let mut game = test_board(6, 1, &[0, 4], &[]);
+        game.reveal(2);
+        assert_eq!(Solver::new(&game).hint(&game).map(|hint| hint.deduction), Some(Deduction::Mine(0)));
+
+        game.toggle_flag(0);
+        assert_eq!(Solver::new(&game).hint(&game).map(|hint| hint.deduction), Some(Deduction::Mine(4)));
+
+        game.toggle_flag(4);
+        assert_eq!(Solver::new(&game).hint(&game), None);
+    }
+
+    #[test]
+    fn is_solvable_when_logic_clears_the_board() {
+        // * 1 0 0: the opening shows the 1, which can only mean the corner
+        let game = test_board(4, 1, &[0], &[]);
+        assert!(Solver::is_solvable(game.neighbor_sets(), game.cells(), 3));
+    }
+
+    #[test]
+    fn is_not_solvable_when_a_guess_is_needed() {
+        // 0 1 * 1 0: the far side is only known by counting the mines, which the solver doesn't do
+        let game = test_board(5, 1, &[2], &[]);
+        assert!(!Solver::is_solvable(game.neighbor_sets(), game.cells(), 0));
+    }
+
+    #[test]
+    fn is_not_solvable_from_a_mine() {
+        let game = test_board(4, 1, &[0], &[]);
+        assert!(!Solver::is_solvable(game.neighbor_sets(), game.cells(), 0));
+    }
+}
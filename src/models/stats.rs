@@ -0,0 +1,215 @@
+use crate::models::settings::{Difficulty, Settings};
+use crate::models::storage::Storage;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const STATS_KEY: &str = "minesweeper_rs.stats";
+const BEST_TIMES_KEPT: usize = 10;
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct BestTime {
+    milliseconds: u64,
+    /// When the game was won, in milliseconds since the Unix epoch.
+    date: u64,
+}
+
+impl BestTime {
+    pub fn milliseconds(&self) -> u64 {
+        self.milliseconds
+    }
+
+    pub fn date(&self) -> u64 {
+        self.date
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DifficultyStats {
+    played: usize,
+    won: usize,
+    current_streak: usize,
+    longest_streak: usize,
+    best_times: Vec<BestTime>,
+}
+
+impl DifficultyStats {
+    pub fn played(&self) -> usize {
+        self.played
+    }
+
+    pub fn won(&self) -> usize {
+        self.won
+    }
+
+    /// Between 0 and 1.
+    pub fn win_rate(&self) -> f64 {
+        if self.played == 0 { 0.0 } else { self.won as f64 / self.played as f64 }
+    }
+
+    pub fn current_streak(&self) -> usize {
+        self.current_streak
+    }
+
+    pub fn longest_streak(&self) -> usize {
+        self.longest_streak
+    }
+
+    pub fn best_time(&self) -> Option<&BestTime> {
+        self.best_times.first()
+    }
+
+    /// The fastest wins, fastest first.
+    pub fn best_times(&self) -> &[BestTime] {
+        &self.best_times
+    }
+
+    pub fn record_win(&mut self, milliseconds: u64, date: u64) {
+        self.played += 1;
+        self.won += 1;
+        self.current_streak += 1;
+        self.longest_streak = self.longest_streak.max(self.current_streak);
+        self.record_time(milliseconds, date);
+    }
+
+    pub fn record_loss(&mut self) {
+        self.played += 1;
+        self.current_streak = 0;
+    }
+
+    // Private methods
+    fn record_time(&mut self, milliseconds: u64, date: u64) {
+        let position = self.best_times.partition_point(|best_time| best_time.milliseconds <= milliseconds);
+        self.best_times.insert(position, BestTime { milliseconds, date });
+        self.best_times.truncate(BEST_TIMES_KEPT);
+    }
+}
+
+/// Results of every finished game, kept separately for each difficulty and variant.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Stats {
+    // Custom games are split up by size, since their times aren't comparable
+    by_difficulty: BTreeMap<String, DifficultyStats>,
+}
+
+impl Stats {
+    pub fn load(storage: &impl Storage) -> Self {
+        storage.load(STATS_KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut impl Storage) {
+        storage.save(STATS_KEY, self);
+    }
+
+    pub fn for_settings(&self, settings: &Settings) -> DifficultyStats {
+        self.by_difficulty.get(&Stats::key(settings)).cloned().unwrap_or_default()
+    }
+
+    pub fn record_win(&mut self, settings: &Settings, milliseconds: u64, date: u64) {
+        self.by_difficulty.entry(Stats::key(settings)).or_default().record_win(milliseconds, date);
+    }
+
+    pub fn record_loss(&mut self, settings: &Settings) {
+        self.by_difficulty.entry(Stats::key(settings)).or_default().record_loss();
+    }
+
+    // Private methods
+    // Variants get their own entries too, e.g. "expert-hex"
+    fn key(settings: &Settings) -> String {
+        let difficulty = match settings.difficulty() {
+            Difficulty::Custom(dimensions) if dimensions.is_layered() => {
+                format!("custom-{}x{}x{}-{}", dimensions.width(), dimensions.height(), dimensions.depth(), dimensions.mines())
+            },
+            Difficulty::Custom(dimensions) => {
+                format!("custom-{}x{}-{}", dimensions.width(), dimensions.height(), dimensions.mines())
+            },
+            difficulty => difficulty.title().to_lowercase(),
+        };
+        match settings.variant() {
+            Some(variant) => format!("{}-{}", difficulty, variant),
+            None => difficulty,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::settings::GenerationSetting;
+    use crate::models::storage::MemoryStorage;
+
+    fn times(stats: &DifficultyStats) -> Vec<u64> {
+        stats.best_times().iter().map(BestTime::milliseconds).collect()
+    }
+
+    #[test]
+    fn a_loss_ends_the_streak_but_keeps_the_longest() {
+        let mut stats = DifficultyStats::default();
+        stats.record_win(3000, 1);
+        stats.record_win(2000, 2);
+        stats.record_loss();
+        stats.record_win(4000, 3);
+
+        assert_eq!(stats.played(), 4);
+        assert_eq!(stats.won(), 3);
+        assert_eq!(stats.current_streak(), 1);
+        assert_eq!(stats.longest_streak(), 2);
+        assert_eq!(stats.win_rate(), 0.75);
+    }
+
+    #[test]
+    fn best_times_are_kept_fastest_first() {
+        let mut stats = DifficultyStats::default();
+        for (date, milliseconds) in [5000, 3000, 4000, 3000].into_iter().enumerate() {
+            stats.record_win(milliseconds, date as u64);
+        }
+
+        assert_eq!(times(&stats), [3000, 3000, 4000, 5000]);
+        // A tie goes after the earlier time
+        assert_eq!(stats.best_times()[0].date(), 1);
+        assert_eq!(stats.best_time().map(BestTime::milliseconds), Some(3000));
+    }
+
+    #[test]
+    fn only_the_ten_best_times_are_kept() {
+        let mut stats = DifficultyStats::default();
+        for milliseconds in (1..=12).rev() {
+            stats.record_win(milliseconds * 1000, 0);
+        }
+        stats.record_win(20_000, 0);
+
+        assert_eq!(times(&stats), (1..=10).map(|seconds| seconds * 1000).collect::<Vec<u64>>());
+        assert_eq!(stats.won(), 13);
+    }
+
+    #[test]
+    fn no_guess_games_are_kept_apart_from_normal_ones() {
+        let normal = Settings::default();
+        let mut no_guess = Settings::default();
+        no_guess.set_generation_setting(GenerationSetting::NoGuess);
+
+        let mut stats = Stats::default();
+        stats.record_win(&no_guess, 1000, 0);
+        stats.record_loss(&normal);
+
+        assert_eq!(stats.for_settings(&no_guess).won(), 1);
+        assert_eq!(stats.for_settings(&normal).won(), 0);
+        assert_eq!(stats.for_settings(&normal).played(), 1);
+    }
+
+    #[test]
+    fn stats_survive_a_save_and_load() {
+        let settings = Settings::default();
+        let mut stats = Stats::default();
+        stats.record_win(&settings, 1500, 42);
+        stats.record_loss(&settings);
+
+        let mut storage = MemoryStorage::default();
+        stats.save(&mut storage);
+        assert_eq!(Stats::load(&storage), stats);
+    }
+
+    #[test]
+    fn missing_stats_load_empty() {
+        assert_eq!(Stats::load(&MemoryStorage::default()), Stats::default());
+    }
+}
@@ -0,0 +1,101 @@
+/// Times a game from its first click to its end.
+///
+/// The caller supplies the time in milliseconds from a monotonic clock, such as `performance.now()`
+/// in the browser, so the elapsed time doesn't drift when timer callbacks are throttled.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stopwatch {
+    started_at: Option<f64>,
+    stopped_at: Option<f64>,
+}
+
+impl Stopwatch {
+    pub fn start(&mut self, now: f64) {
+        self.started_at = Some(now);
+        self.stopped_at = None;
+    }
+
+    pub fn stop(&mut self, now: f64) {
+        if self.is_running() { self.stopped_at = Some(now); }
+    }
+
+    /// Carries on after [`stop`](Self::stop), leaving out the time spent stopped.
+    pub fn resume(&mut self, now: f64) {
+        let (Some(started_at), Some(stopped_at)) = (self.started_at, self.stopped_at) else { return; };
+        self.started_at = Some(started_at + now - stopped_at);
+        self.stopped_at = None;
+    }
+
+    pub fn reset(&mut self) {
+        self.started_at = None;
+        self.stopped_at = None;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started_at.is_some() && self.stopped_at.is_none()
+    }
+
+    pub fn elapsed_milliseconds(&self, now: f64) -> f64 {
+        let Some(started_at) = self.started_at else { return 0.0; };
+        (self.stopped_at.unwrap_or(now) - started_at).max(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_unstarted_stopwatch_reads_zero() {
+        let stopwatch = Stopwatch::default();
+
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_milliseconds(5000.0), 0.0);
+    }
+
+    #[test]
+    fn a_running_stopwatch_counts_from_its_start() {
+        let mut stopwatch = Stopwatch::default();
+        stopwatch.start(1000.0);
+
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_milliseconds(1250.5), 250.5);
+        // A clock that reads earlier than the start never gives a negative time
+        assert_eq!(stopwatch.elapsed_milliseconds(900.0), 0.0);
+    }
+
+    #[test]
+    fn a_stopped_stopwatch_holds_its_time() {
+        let mut stopwatch = Stopwatch::default();
+        stopwatch.start(1000.0);
+        stopwatch.stop(3000.0);
+        stopwatch.stop(4000.0);
+
+        assert!(!stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_milliseconds(9000.0), 2000.0);
+    }
+
+    #[test]
+    fn resuming_leaves_out_the_time_spent_stopped() {
+        let mut stopwatch = Stopwatch::default();
+        stopwatch.start(1000.0);
+        stopwatch.stop(3000.0);
+        stopwatch.resume(10_000.0);
+
+        assert!(stopwatch.is_running());
+        assert_eq!(stopwatch.elapsed_milliseconds(10_500.0), 2500.0);
+    }
+
+    #[test]
+    fn resuming_does_nothing_unless_stopped() {
+        let mut stopwatch = Stopwatch::default();
+        stopwatch.resume(500.0);
+        assert!(!stopwatch.is_running());
+
+        stopwatch.start(1000.0);
+        stopwatch.resume(2000.0);
+        assert_eq!(stopwatch.elapsed_milliseconds(3000.0), 2000.0);
+
+        stopwatch.reset();
+        assert_eq!(stopwatch, Stopwatch::default());
+    }
+}
@@ -0,0 +1,75 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// String key-value persistence, such as the browser's `localStorage`.
+pub trait Storage {
+    fn get(&self, key: &str) -> Option<String>;
+    fn set(&mut self, key: &str, value: &str);
+    fn remove(&mut self, key: &str);
+
+    /// Reads `key` as JSON, treating anything missing or unreadable as absent.
+    fn load<T: DeserializeOwned>(&self, key: &str) -> Option<T> where Self: Sized {
+        self.get(key).and_then(|value| serde_json::from_str(&value).ok())
+    }
+
+    fn save<T: Serialize>(&mut self, key: &str, value: &T) where Self: Sized {
+        match serde_json::to_string(value) {
+            Ok(json) => self.set(key, &json),
+            Err(error) => log::warn!("Couldn't save {}: {}", key, error),
+        }
+    }
+}
+
+/// Keeps everything in memory, for tests and frontends without persistent storage.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MemoryStorage {
+    values: HashMap<String, String>,
+}
+
+impl Storage for MemoryStorage {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+
+    fn set(&mut self, key: &str, value: &str) {
+        self.values.insert(key.to_string(), value.to_string());
+    }
+
+    fn remove(&mut self, key: &str) {
+        self.values.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_values_load_back() {
+        let mut storage = MemoryStorage::default();
+        storage.save("key", &vec![1, 2, 3]);
+
+        assert_eq!(storage.load::<Vec<u32>>("key"), Some(vec![1, 2, 3]));
+        assert_eq!(storage.get("key").as_deref(), Some("[1,2,3]"));
+    }
+
+    #[test]
+    fn removed_values_are_gone() {
+        let mut storage = MemoryStorage::default();
+        storage.set("key", "1");
+        storage.remove("key");
+
+        assert_eq!(storage.get("key"), None);
+        assert_eq!(storage.load::<u32>("key"), None);
+    }
+
+    #[test]
+    fn unreadable_values_load_as_absent() {
+        let mut storage = MemoryStorage::default();
+        storage.set("key", "not json");
+
+        assert_eq!(storage.load::<u32>("key"), None);
+        assert_eq!(storage.load::<u32>("other"), None);
+    }
+}
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+
+const SQUARE_OFFSETS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+// Odd rows sit half a cell to the right, so the diagonals lean the other way on even rows
+const HEX_EVEN_ROW_OFFSETS: [(isize, isize); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+const HEX_ODD_ROW_OFFSETS: [(isize, isize); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+/// The shape of the grid, which decides which cells are next to each other.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Topology {
+    #[default]
+    Square,
+    /// Rows of hexagons with every odd row shifted half a cell to the right.
+    Hex,
+}
+
+impl Topology {
+    pub fn title(&self) -> String {
+        match self {
+            Topology::Square => { "Square".into() },
+            Topology::Hex => { "Hexagonal".into() },
+        }
+    }
+
+    /// The `(row, column)` offsets from a cell in `row` to each of its neighbors.
+    pub fn neighbor_offsets(&self, row: usize) -> &'static [(isize, isize)] {
+        match self {
+            Topology::Square => { &SQUARE_OFFSETS },
+            Topology::Hex if row % 2 == 0 => { &HEX_EVEN_ROW_OFFSETS },
+            Topology::Hex => { &HEX_ODD_ROW_OFFSETS },
+        }
+    }
+
+    /// The most neighbors any one cell can have.
+    pub fn max_neighbors(&self) -> usize {
+        self.neighbor_offsets(0).len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn squares_have_eight_neighbors_and_hexagons_six() {
+        assert_eq!(Topology::Square.max_neighbors(), 8);
+        assert_eq!(Topology::Hex.max_neighbors(), 6);
+        assert_eq!(Topology::Square.neighbor_offsets(1), Topology::Square.neighbor_offsets(0));
+        assert_ne!(Topology::Hex.neighbor_offsets(1), Topology::Hex.neighbor_offsets(0));
+    }
+}
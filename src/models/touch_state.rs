@@ -0,0 +1,92 @@
+/// How long a finger has to rest on a cell before it counts as a long press.
+pub const LONG_PRESS_MILLISECONDS: u32 = 400;
+// Fingers wobble, so only movement beyond this many pixels counts as a drag
+const DRAG_TOLERANCE: f64 = 10.0;
+
+/// What tapping a hidden cell does; a long press does the other one.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum InputMode {
+    #[default]
+    Dig,
+    Flag,
+}
+
+impl InputMode {
+    pub fn toggled(self) -> Self {
+        match self {
+            InputMode::Dig => { InputMode::Flag },
+            InputMode::Flag => { InputMode::Dig },
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match self {
+            InputMode::Dig => { "Dig".into() },
+            InputMode::Flag => { "Flag".into() },
+        }
+    }
+}
+
+/// A finger resting on a cell, from `touchstart` until it's lifted, dragged away or held long enough.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TouchPress {
+    index: usize,
+    x: f64,
+    y: f64,
+    long_pressed: bool,
+}
+
+impl TouchPress {
+    pub fn new(index: usize, x: f64, y: f64) -> Self {
+        TouchPress { index, x, y, long_pressed: false }
+    }
+
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Whether the finger has moved from `(x, y)` too far to still be a tap.
+    pub fn is_drag(&self, x: f64, y: f64) -> bool {
+        (x - self.x).hypot(y - self.y) > DRAG_TOLERANCE
+    }
+
+    pub fn is_long_pressed(&self) -> bool {
+        self.long_pressed
+    }
+
+    pub fn long_press(&mut self) {
+        self.long_pressed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_wobbles_are_not_drags() {
+        let press = TouchPress::new(3, 100.0, 200.0);
+
+        assert!(!press.is_drag(100.0, 200.0));
+        assert!(!press.is_drag(106.0, 208.0));
+        assert!(!press.is_drag(90.0, 200.0));
+        assert!(press.is_drag(107.0, 208.0));
+        assert!(press.is_drag(100.0, 189.0));
+    }
+
+    #[test]
+    fn long_presses_are_remembered() {
+        let mut press = TouchPress::new(3, 0.0, 0.0);
+        assert!(!press.is_long_pressed());
+
+        press.long_press();
+        assert!(press.is_long_pressed());
+        assert_eq!(press.index(), 3);
+    }
+
+    #[test]
+    fn input_modes_toggle_back_and_forth() {
+        assert_eq!(InputMode::default().toggled(), InputMode::Flag);
+        assert_eq!(InputMode::Flag.toggled(), InputMode::Dig);
+    }
+}
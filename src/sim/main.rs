@@ -0,0 +1,226 @@
+//! Generates a batch of boards and reports what they were like, to help pick fair custom presets
+//! and to check changes to how boards are generated.
+
+use minesweeper_rs::models::settings::{Difficulty, FirstClickSetting, GenerationSetting, Settings};
+use minesweeper_rs::models::simulation::{Distribution, FirstClick, SimulationReport};
+use std::process::ExitCode;
+use std::time::Instant;
+use std::{env, thread};
+
+const USAGE: &str = "\
+Usage: minesweeper_sim [OPTIONS]
+
+Options:
+  --difficulty NAME     beginner, intermediate or expert (default: expert)
+  --size WxH[xD]        a custom board size, used with --mines
+  --mines N             how many mines a custom board has
+  --first-click RULE    any, safe or zero (default: zero)
+  --no-guess            only generate boards that can be solved without guessing
+  --skip-solver         don't judge which boards need guessing, which is most of the work
+  --click WHERE         random, center, corner or a cell index (default: random)
+  --boards N            how many boards to generate (default: 10000)
+  --seed N              the seed of the first board; the rest follow on from it (default: random)
+  --threads N           how many threads to generate on (default: all of them)
+  --help                show this message";
+const HISTOGRAM_ROWS: usize = 16;
+const HISTOGRAM_WIDTH: f64 = 50.0;
+
+struct Options {
+    settings: Settings,
+    first_click: FirstClick,
+    judge_solvable: bool,
+    boards: u64,
+    seed: u64,
+    threads: usize,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut settings = Settings::default();
+    settings.set_difficulty(Difficulty::Expert);
+    let mut size: Option<(usize, usize, usize)> = None;
+    let mut mines: Option<usize> = None;
+    let mut first_click = FirstClick::default();
+    let mut judge_solvable = true;
+    let mut boards = 10_000;
+    let mut seed = rand::random();
+    let mut threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+
+    while let Some(arg) = args.next() {
+        if arg == "--help" || arg == "-h" { return Ok(None); }
+        if arg == "--no-guess" {
+            settings.set_generation_setting(GenerationSetting::NoGuess);
+            continue;
+        }
+        if arg == "--skip-solver" {
+            judge_solvable = false;
+            continue;
+        }
+
+        let value = args.next().ok_or_else(|| format!("{} needs a value", arg))?;
+        match arg.as_str() {
+            "--difficulty" => {
+                let difficulty = match value.as_str() {
+                    "beginner" => Difficulty::Beginner,
+                    "intermediate" => Difficulty::Intermediate,
+                    "expert" => Difficulty::Expert,
+                    _ => return Err(format!("unknown difficulty {:?}", value)),
+                };
+                settings.set_difficulty(difficulty);
+            },
+            "--size" => {
+                let sides = value
+                    .split('x')
+                    .map(|side| side.parse::<usize>().map_err(|_| format!("invalid size {:?}", value)))
+                    .collect::<Result<Vec<usize>, String>>()?;
+                size = match sides[..] {
+                    [width, height] => Some((width, height, 1)),
+                    [width, height, depth] => Some((width, height, depth)),
+                    _ => return Err(format!("invalid size {:?}", value)),
+                };
+            },
+            "--mines" => { mines = Some(parse_number(&arg, &value)?); },
+            "--first-click" => {
+                let first_click_setting = match value.as_str() {
+                    "any" => FirstClickSetting::Any,
+                    "safe" => FirstClickSetting::Safe,
+                    "zero" => FirstClickSetting::Zero,
+                    _ => return Err(format!("unknown first click rule {:?}", value)),
+                };
+                settings.set_first_click_setting(first_click_setting);
+            },
+            "--click" => {
+                first_click = match value.as_str() {
+                    "random" => FirstClick::Random,
+                    "center" => FirstClick::Center,
+                    "corner" => FirstClick::Corner,
+                    _ => FirstClick::Index(parse_number(&arg, &value)?),
+                };
+            },
+            "--boards" => { boards = parse_number(&arg, &value)?; },
+            "--seed" => { seed = parse_number(&arg, &value)?; },
+            "--threads" => { threads = parse_number::<usize>(&arg, &value)?.max(1); },
+            _ => return Err(format!("unknown option {:?}", arg)),
+        }
+    }
+
+    // Checked last, since the first click rule changes how many mines fit
+    match (size, mines) {
+        (Some((width, height, depth)), Some(mines)) => {
+            let dimensions = settings.validate_dimensions(width, height, depth, mines).map_err(|error| error.to_string())?;
+            settings.set_difficulty(Difficulty::Custom(dimensions));
+        },
+        (None, None) => {},
+        _ => return Err("--size and --mines go together".into()),
+    }
+
+    Ok(Some(Options { settings, first_click, judge_solvable, boards, seed, threads }))
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("{} needs a whole number, not {:?}", option, value))
+}
+
+// Each thread takes its own run of seeds and reports only add up whole numbers, so the results don't depend on how many threads there are
+fn simulate(options: &Options) -> SimulationReport {
+    let threads = (options.threads as u64).min(options.boards.max(1));
+    let per_thread = options.boards / threads;
+    let remainder = options.boards % threads;
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|thread_index| {
+                let start = (thread_index * per_thread) + thread_index.min(remainder);
+                let boards = per_thread + u64::from(thread_index < remainder);
+                let seed = options.seed.wrapping_add(start);
+                scope.spawn(move || SimulationReport::run(options.settings, options.first_click, options.judge_solvable, seed, boards))
+            })
+            .collect();
+
+        handles.into_iter().fold(SimulationReport::default(), |mut report, handle| {
+            report.merge(&handle.join().expect("a simulation thread panicked"));
+            report
+        })
+    })
+}
+
+fn print_report(options: &Options, report: &SimulationReport, seconds: f64) {
+    let settings = &options.settings;
+    let dimensions = settings.dimensions();
+    let size = if dimensions.is_layered() {
+        format!("{}x{}x{}", dimensions.width(), dimensions.height(), dimensions.depth())
+    } else {
+        format!("{}x{}", dimensions.width(), dimensions.height())
+    };
+    let cells = dimensions.cell_count() as f64;
+
+    println!("Boards:       {} of {} with {} mines ({:.1}% density), seeds from {}", report.boards(), size, dimensions.mines(), dimensions.mines() as f64 * 100.0 / cells, options.seed);
+    println!("First click:  {}{}, clicking {:?}", settings.first_click_setting().title().to_lowercase(), if settings.generation_setting_is_no_guess() { ", no guess" } else { "" }, options.first_click);
+    println!("3BV:          {}", summarize(report.three_bv()));
+    println!("Openings:     {}", summarize(report.openings()));
+    match report.solvable_fraction() {
+        // Generation already threw away boards that needed guessing, so only the ones it gave up on can fail
+        Some(fraction) if settings.generation_setting_is_no_guess() => {
+            println!("No guessing:  {:.2}% solvable by logic from the first click, with boards already filtered for it; the rest ran out of tries", fraction * 100.0)
+        },
+        Some(fraction) => println!("No guessing:  {:.2}% solvable by logic from the first click", fraction * 100.0),
+        None if !options.judge_solvable => println!("No guessing:  not judged, the solver was skipped"),
+        None => println!("No guessing:  not judged, the solver needs one mine per cell"),
+    }
+    println!("Near click:   {:.2}% of the first clicks' neighbors hold mines", report.first_click_density() * 100.0);
+    println!("Click mines:  {:.2}% of first clicks hit a mine", report.first_click_mine_fraction() * 100.0);
+    println!("Took:         {:.1}s ({:.0} boards/s)", seconds, report.boards() as f64 / seconds.max(f64::EPSILON));
+    println!();
+    println!("3BV distribution:");
+    print_histogram(report.three_bv());
+}
+
+fn summarize(distribution: &Distribution) -> String {
+    let percentile = |fraction| distribution.percentile(fraction).unwrap_or_default();
+    format!(
+        "mean {:.2}, min {}, 10% {}, median {}, 90% {}, max {}",
+        distribution.mean(),
+        distribution.min().unwrap_or_default(),
+        percentile(0.1),
+        percentile(0.5),
+        percentile(0.9),
+        distribution.max().unwrap_or_default(),
+    )
+}
+
+// Groups the values into evenly sized buckets so wide ranges still fit on screen
+fn print_histogram(distribution: &Distribution) {
+    let (Some(min), Some(max)) = (distribution.min(), distribution.max()) else { return; };
+    let bucket_size = ((max - min) / HISTOGRAM_ROWS) + 1;
+    let mut buckets = vec![0u64; ((max - min) / bucket_size) + 1];
+    for (value, count) in distribution.counts() {
+        buckets[(value - min) / bucket_size] += count;
+    }
+
+    let total = distribution.total() as f64;
+    let tallest = buckets.iter().copied().max().unwrap_or(1) as f64;
+    for (bucket, count) in buckets.iter().enumerate() {
+        let low = min + (bucket * bucket_size);
+        let label = if bucket_size == 1 { low.to_string() } else { format!("{}-{}", low, low + bucket_size - 1) };
+        let bar = "#".repeat((*count as f64 / tallest * HISTOGRAM_WIDTH).round() as usize);
+        println!("  {:>9} {:>6.2}% {}", label, *count as f64 * 100.0 / total, bar);
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match parse_options(env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Err(error) => {
+            eprintln!("error: {}\n\n{}", error, USAGE);
+            return ExitCode::from(2);
+        },
+    };
+
+    let started = Instant::now();
+    let report = simulate(&options);
+    print_report(&options, &report, started.elapsed().as_secs_f64());
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,352 @@
+//! Plays the same rules as the browser frontend in a terminal, with the mouse or the keyboard.
+
+mod view;
+
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute};
+use minesweeper_rs::models::face::Face;
+use minesweeper_rs::models::game::{Clock, Game, GameState};
+use minesweeper_rs::models::key_bindings::{KeyAction, KeyBindings};
+use minesweeper_rs::models::settings::{Difficulty, Settings};
+use minesweeper_rs::models::stopwatch::Stopwatch;
+use std::io::{self, Write};
+use std::panic;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Only the timer changes on its own, so redrawing this often is plenty
+const TICK: Duration = Duration::from_millis(100);
+const DIFFICULTIES: [Difficulty; 3] = [Difficulty::Beginner, Difficulty::Intermediate, Difficulty::Expert];
+
+pub struct App {
+    game:               Game,
+    face:               Face,
+    stopwatch:          Stopwatch,
+    key_bindings:       KeyBindings,
+    cursor_index:       Option<usize>,
+    pressed_index:      Option<usize>,
+    started:            Instant,
+    quit:               bool,
+}
+
+impl App {
+    fn new() -> Self {
+        let mut game = Game::new(Settings::default());
+        game.set_clock(Clock(system_milliseconds));
+        App {
+            game,
+            face: Face::Happy,
+            stopwatch: Stopwatch::default(),
+            key_bindings: KeyBindings::default(),
+            cursor_index: None,
+            pressed_index: None,
+            started: Instant::now(),
+            quit: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn face(&self) -> Face {
+        self.face
+    }
+
+    pub fn cursor_index(&self) -> Option<usize> {
+        self.cursor_index
+    }
+
+    pub fn pressed_index(&self) -> Option<usize> {
+        self.pressed_index
+    }
+
+    pub fn elapsed_milliseconds(&self) -> f64 {
+        self.stopwatch.elapsed_milliseconds(self.now())
+    }
+
+    // The layer holding the cursor, which is the one on screen
+    pub fn layer(&self) -> usize {
+        self.cursor_index.map_or(0, |index| self.game.get_layer_from_index(index))
+    }
+
+    // Private methods
+    fn now(&self) -> f64 {
+        self.started.elapsed().as_secs_f64() * 1000.0
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(key_event) if key_event.kind == KeyEventKind::Press => { self.handle_key(key_event) },
+            Event::Mouse(mouse_event) => { self.handle_mouse(mouse_event) },
+            _ => {},
+        }
+    }
+
+    fn handle_key(&mut self, key_event: KeyEvent) {
+        let is_ctrl_c = key_event.code == KeyCode::Char('c') && key_event.modifiers.contains(KeyModifiers::CONTROL);
+        if is_ctrl_c || matches!(key_event.code, KeyCode::Char('q') | KeyCode::Esc) {
+            self.quit = true;
+            return;
+        }
+        if let KeyCode::Char(c @ '1'..='3') = key_event.code {
+            self.handle_change_size(DIFFICULTIES[c as usize - '1' as usize]);
+            return;
+        }
+
+        let Some(key) = dom_key(key_event.code) else { return; };
+        let Some(action) = self.key_bindings.action(&key) else { return; };
+        if action == KeyAction::Reset {
+            self.handle_reset();
+            return;
+        }
+        // The first key press only shows the cursor
+        let Some(index) = self.cursor_index else {
+            self.cursor_index = Some(self.game.settings().dimensions().layer_cell_count() / 2);
+            return;
+        };
+
+        match action {
+            KeyAction::Up => { self.move_cursor(index, 0, -1, 0) },
+            KeyAction::Down => { self.move_cursor(index, 0, 1, 0) },
+            KeyAction::Left => { self.move_cursor(index, 0, 0, -1) },
+            KeyAction::Right => { self.move_cursor(index, 0, 0, 1) },
+            KeyAction::PreviousLayer => { self.move_cursor(index, -1, 0, 0) },
+            KeyAction::NextLayer => { self.move_cursor(index, 1, 0, 0) },
+            KeyAction::Reveal => { self.handle_click(index) },
+            KeyAction::Flag => { self.handle_flag(index) },
+            KeyAction::Chord => { self.handle_chord(index) },
+            KeyAction::Reset => {},
+        }
+    }
+
+    // Left clicks act when the button comes back up over the same cell, like in the browser
+    fn handle_mouse(&mut self, mouse_event: MouseEvent) {
+        let index = view::index_at(self, mouse_event.column, mouse_event.row);
+        match mouse_event.kind {
+            MouseEventKind::Down(MouseButton::Left) | MouseEventKind::Down(MouseButton::Middle) => {
+                if !self.game.is_active() { return; }
+                self.pressed_index = index;
+                self.face = Face::Nervous;
+            },
+            MouseEventKind::Down(MouseButton::Right) => {
+                if let Some(index) = index { self.handle_flag(index); }
+            },
+            MouseEventKind::Up(button) => {
+                let pressed_index = self.pressed_index.take();
+                if self.game.is_active() { self.face = Face::Happy; }
+                let Some(index) = index.filter(|index| Some(*index) == pressed_index) else { return; };
+                match button {
+                    MouseButton::Left => { self.handle_click(index) },
+                    MouseButton::Middle => { self.handle_chord(index) },
+                    MouseButton::Right => {},
+                }
+            },
+            _ => {},
+        }
+    }
+
+    // The cursor stops at the edges of the board
+    fn move_cursor(&mut self, index: usize, layer_delta: isize, row_delta: isize, col_delta: isize) {
+        let layer = self.game.get_layer_from_index(index) as isize;
+        let (row, col) = self.game.get_row_col_from_index(index);
+        if let Some(index) = self.game.get_index_from_layer_row_col(layer + layer_delta, row as isize + row_delta, col as isize + col_delta) {
+            self.cursor_index = Some(index);
+        }
+    }
+
+    // Clicking a shown number chords, as long as chording is on the left button
+    fn handle_click(&mut self, index: usize) {
+        let chords_on_click = self.game.settings().chord_setting().chords_on_left_click();
+        if self.game.cell(index).is_shown() && chords_on_click {
+            self.handle_chord(index);
+            return;
+        }
+
+        let is_first_click = !self.game.has_started();
+        if self.game.reveal(index) && is_first_click { self.stopwatch.start(self.now()); }
+        self.handle_game_state();
+    }
+
+    fn handle_flag(&mut self, index: usize) {
+        self.game.toggle_flag(index);
+    }
+
+    fn handle_chord(&mut self, index: usize) {
+        self.game.chord(index);
+        self.handle_game_state();
+    }
+
+    fn handle_change_size(&mut self, difficulty: Difficulty) {
+        self.game.set_difficulty(difficulty);
+        self.cursor_index = None;
+        self.handle_reset();
+    }
+
+    fn handle_reset(&mut self) {
+        self.game.reset();
+        self.stopwatch.reset();
+        self.pressed_index = None;
+        self.face = Face::Happy;
+    }
+
+    fn handle_game_state(&mut self) {
+        self.face = match self.game.state() {
+            GameState::Won => Face::Cool,
+            GameState::Lost => Face::Dead,
+            GameState::Ready | GameState::Playing => Face::Happy,
+        };
+        if !self.game.is_active() && self.stopwatch.is_running() { self.stopwatch.stop(self.now()); }
+    }
+}
+
+// Only used to time board generation, which the wall clock is steady enough for
+fn system_milliseconds() -> f64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0.0, |duration| duration.as_secs_f64() * 1000.0)
+}
+
+/// The DOM's name for `code`, so the browser's [`KeyBindings`] work here too.
+fn dom_key(code: KeyCode) -> Option<String> {
+    let key = match code {
+        KeyCode::Char(c) => { return Some(c.to_string()); },
+        KeyCode::Up => "ArrowUp",
+        KeyCode::Down => "ArrowDown",
+        KeyCode::Left => "ArrowLeft",
+        KeyCode::Right => "ArrowRight",
+        KeyCode::PageUp => "PageUp",
+        KeyCode::PageDown => "PageDown",
+        KeyCode::Enter => "Enter",
+        KeyCode::Tab => "Tab",
+        KeyCode::Backspace => "Backspace",
+        _ => { return None; },
+    };
+    Some(key.to_string())
+}
+
+fn run(stdout: &mut impl Write) -> io::Result<()> {
+    let mut app = App::new();
+    view::clear(stdout)?;
+    while !app.quit {
+        view::draw(stdout, &app)?;
+        if !event::poll(TICK)? { continue; }
+
+        let event = event::read()?;
+        let difficulty = app.game.settings().difficulty();
+        app.handle_event(event.clone());
+        // A smaller board would leave the old one behind
+        if app.game.settings().difficulty() != difficulty || matches!(event, Event::Resize(..)) { view::clear(stdout)?; }
+    }
+    Ok(())
+}
+
+/// Puts the terminal back when dropped, however the game ended, including when setting it up fails halfway.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new(stdout: &mut impl Write) -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(stdout, EnterAlternateScreen, event::EnableMouseCapture, cursor::Hide)?;
+        Ok(guard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+// Errors are ignored, since there's nothing left to do about a terminal that won't reset
+fn restore_terminal() {
+    let _ = execute!(io::stdout(), cursor::Show, event::DisableMouseCapture, LeaveAlternateScreen);
+    let _ = terminal::disable_raw_mode();
+}
+
+fn main() -> io::Result<()> {
+    // Leave the alternate screen before a panic message is printed, or it's lost along with it
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+
+    let mut stdout = io::stdout();
+    let _guard = TerminalGuard::new(&mut stdout)?;
+    run(&mut stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    #[test]
+    fn terminal_keys_get_their_dom_names() {
+        assert_eq!(dom_key(KeyCode::Char(' ')).as_deref(), Some(" "));
+        assert_eq!(dom_key(KeyCode::Left).as_deref(), Some("ArrowLeft"));
+        assert_eq!(dom_key(KeyCode::PageDown).as_deref(), Some("PageDown"));
+        assert_eq!(dom_key(KeyCode::F(1)), None);
+    }
+
+    #[test]
+    fn the_first_key_shows_the_cursor_and_the_next_ones_move_it() {
+        let mut app = App::new();
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.cursor_index(), Some(40));
+
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Char('h'));
+        assert_eq!(app.cursor_index(), Some(30));
+    }
+
+    #[test]
+    fn the_cursor_stops_at_the_edges() {
+        let mut app = App::new();
+        press(&mut app, KeyCode::Char('j'));
+        for _ in 0..10 {
+            press(&mut app, KeyCode::Right);
+            press(&mut app, KeyCode::PageDown);
+        }
+        assert_eq!(app.cursor_index(), Some(44));
+    }
+
+    #[test]
+    fn revealing_starts_the_game_and_the_timer() {
+        let mut app = App::new();
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Char(' '));
+
+        assert!(app.game().has_started());
+        assert!(app.game().cell(40).is_shown());
+        assert!(app.stopwatch.is_running());
+
+        press(&mut app, KeyCode::Char('r'));
+        assert!(!app.game().has_started());
+        assert_eq!(app.elapsed_milliseconds(), 0.0);
+    }
+
+    #[test]
+    fn number_keys_pick_a_difficulty() {
+        let mut app = App::new();
+        press(&mut app, KeyCode::Char('2'));
+
+        assert_eq!(app.game().settings().difficulty(), Difficulty::Intermediate);
+        assert_eq!(app.cursor_index(), None);
+    }
+
+    #[test]
+    fn quit_keys_quit() {
+        for code in [KeyCode::Char('q'), KeyCode::Esc] {
+            let mut app = App::new();
+            press(&mut app, code);
+            assert!(app.quit);
+        }
+        let mut app = App::new();
+        app.handle_key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        assert!(app.quit);
+    }
+}
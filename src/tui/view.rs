@@ -0,0 +1,141 @@
+use crate::{App, DIFFICULTIES};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{cursor, queue};
+use minesweeper_rs::models::cell::Cell;
+use std::cmp;
+use std::io::{self, Write};
+
+// Every cell is three columns wide so emoji flags still line up
+const CELL_WIDTH: u16 = 3;
+const BOARD_TOP: u16 = 4;
+const HIDDEN_BACKGROUND: Color = Color::DarkGrey;
+const SHOWN_BACKGROUND: Color = Color::Grey;
+const HELP: &str = "Arrows/hjkl move  Space reveal  f flag  d chord  Mouse: left reveal, right flag, middle chord";
+
+pub fn clear(out: &mut impl Write) -> io::Result<()> {
+    queue!(out, Clear(ClearType::All))
+}
+
+pub fn draw(out: &mut impl Write, app: &App) -> io::Result<()> {
+    let game = app.game();
+    let dimensions = game.settings().dimensions();
+
+    queue!(out, cursor::MoveTo(0, 0))?;
+    for (number, difficulty) in DIFFICULTIES.iter().enumerate() {
+        let attribute = if *difficulty == game.settings().difficulty() { Attribute::Reverse } else { Attribute::NormalIntensity };
+        queue!(out, SetAttribute(attribute), Print(format!(" {} {} ", number + 1, difficulty.title())), SetAttribute(Attribute::Reset), Print(" "))?;
+    }
+    queue!(out, Print(" r New game  q Quit"), Clear(ClearType::UntilNewLine))?;
+
+    let mines_remaining = cmp::max(game.mines_count() as isize - game.count_flagged_mines() as isize, -99);
+    let seconds = cmp::min(app.elapsed_milliseconds() as usize / 1000, 999);
+    let board_width = dimensions.width() * CELL_WIDTH as usize;
+    let header = format!("{}{:^width$}{:0>3}", counter(mines_remaining), app.face().to_str(), seconds, width = board_width.saturating_sub(7));
+    queue!(out, cursor::MoveTo(0, 2), Print(header), Clear(ClearType::UntilNewLine))?;
+    if dimensions.is_layered() {
+        queue!(out, Print(format!("  layer {} of {}", app.layer() + 1, dimensions.depth())))?;
+    }
+
+    let layer_offset = app.layer() * dimensions.layer_cell_count();
+    for row in 0..dimensions.height() {
+        queue!(out, cursor::MoveTo(0, BOARD_TOP + row as u16))?;
+        for col in 0..dimensions.width() {
+            let index = layer_offset + (row * dimensions.width()) + col;
+            draw_cell(out, app, index, game.cell(index))?;
+        }
+        queue!(out, ResetColor, Clear(ClearType::UntilNewLine))?;
+    }
+
+    let footer = if game.is_won() {
+        format!("Cleared in {:.3} seconds", app.elapsed_milliseconds() / 1000.0)
+    } else {
+        HELP.to_string()
+    };
+    queue!(out, cursor::MoveTo(0, BOARD_TOP + dimensions.height() as u16 + 1), Print(footer), Clear(ClearType::UntilNewLine))?;
+    out.flush()
+}
+
+/// The cell under the terminal position `(column, row)`, if there is one.
+pub fn index_at(app: &App, column: u16, row: u16) -> Option<usize> {
+    let dimensions = app.game().settings().dimensions();
+    let col = (column / CELL_WIDTH) as usize;
+    let row = (row.checked_sub(BOARD_TOP)?) as usize;
+    if col >= dimensions.width() || row >= dimensions.height() { return None; }
+
+    Some((app.layer() * dimensions.layer_cell_count()) + (row * dimensions.width()) + col)
+}
+
+fn draw_cell(out: &mut impl Write, app: &App, index: usize, cell: &Cell) -> io::Result<()> {
+    let game = app.game();
+    let is_pressed = app.pressed_index() == Some(index) && !cell.is_flagged();
+    let background = if app.cursor_index() == Some(index) {
+        Color::DarkYellow
+    } else if game.first_clicked_mine_index() == Some(index) {
+        Color::Red
+    } else if cell.is_shown() || is_pressed {
+        SHOWN_BACKGROUND
+    } else {
+        HIDDEN_BACKGROUND
+    };
+    let foreground = if cell.is_shown() { number_color(&cell.value().get_name_string()) } else { Color::White };
+
+    queue!(out, SetBackgroundColor(background), SetForegroundColor(foreground), Print(centered(&cell.get_value_display_string())))
+}
+
+// Matches the number colors of the browser's stylesheet
+fn number_color(name: &str) -> Color {
+    match name {
+        "one" => Color::Blue,
+        "two" => Color::DarkGreen,
+        "three" => Color::Red,
+        "four" => Color::DarkMagenta,
+        "five" => Color::DarkRed,
+        "six" => Color::DarkCyan,
+        "seven" => Color::Black,
+        "eight" => Color::DarkGrey,
+        "many" => Color::DarkYellow,
+        _ => Color::Black,
+    }
+}
+
+fn centered(text: &str) -> String {
+    // Emoji take up two columns
+    let width: usize = text.chars().map(|c| if c as u32 >= 0x1F000 { 2 } else { 1 }).sum();
+    let padding = (CELL_WIDTH as usize).saturating_sub(width);
+    format!("{}{}{}", " ".repeat(padding / 2), text, " ".repeat(padding - (padding / 2)))
+}
+
+// Zero padding goes after the sign, so -5 reads "-05" rather than "0-5"
+fn counter(value: isize) -> String {
+    if value < 0 { format!("-{:0>2}", value.unsigned_abs()) } else { format!("{:0>3}", value) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counters_keep_three_characters() {
+        assert_eq!(counter(7), "007");
+        assert_eq!(counter(123), "123");
+        assert_eq!(counter(-5), "-05");
+        assert_eq!(counter(-99), "-99");
+    }
+
+    #[test]
+    fn cells_are_centered_and_emoji_count_double() {
+        assert_eq!(centered("1"), " 1 ");
+        assert_eq!(centered("🚩"), "🚩 ");
+    }
+
+    #[test]
+    fn clicks_land_on_the_cell_under_them() {
+        let app = App::new();
+
+        assert_eq!(index_at(&app, 0, BOARD_TOP), Some(0));
+        assert_eq!(index_at(&app, (2 * CELL_WIDTH) + 2, BOARD_TOP + 1), Some(11));
+        assert_eq!(index_at(&app, 0, BOARD_TOP - 1), None);
+        assert_eq!(index_at(&app, 9 * CELL_WIDTH, BOARD_TOP), None);
+    }
+}
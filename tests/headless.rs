@@ -0,0 +1,39 @@
+//! Plays through the library's public API alone, as a frontend without a browser would.
+
+use minesweeper_rs::{BoardCode, Difficulty, Dimensions, FirstClickSetting, Game, GameState, Settings};
+
+fn settings() -> Settings {
+    let mut settings = Settings::default();
+    settings.set_first_click_setting(FirstClickSetting::Any);
+    settings.set_difficulty(Difficulty::Custom(Dimensions::new(4, 1, 1)));
+    settings
+}
+
+#[test]
+fn a_game_can_be_won_without_a_frontend() {
+    let mut game = Game::new(settings());
+    game.load_board(&BoardCode::new(Dimensions::new(4, 1, 0), vec![3], None)).expect("the layout fits");
+
+    assert!(game.reveal(0));
+    assert_eq!(game.state(), GameState::Won);
+    assert!(game.cell(3).is_flagged());
+}
+
+#[test]
+fn a_game_can_be_lost_without_a_frontend() {
+    let mut game = Game::new(settings());
+    game.load_board(&BoardCode::new(Dimensions::new(4, 1, 0), vec![3], None)).expect("the layout fits");
+
+    assert!(game.reveal(3));
+    assert_eq!(game.state(), GameState::Lost);
+}
+
+#[test]
+fn generated_games_keep_to_their_settings() {
+    let mut game = Game::new(settings());
+    game.reset_with_seed(7);
+    game.reveal(0);
+
+    assert_eq!(game.mines_count(), 1);
+    assert_eq!(game.cells().len(), 4);
+}